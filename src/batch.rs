@@ -0,0 +1,197 @@
+//! Batch mode, which evaluates one expression per line of stdin or a file.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, BufRead, Write},
+};
+
+use unit_parser::Environment;
+
+use crate::output::{self, DisplayOptions, OutputFormat};
+
+/// How many expressions a batch evaluated, and how many of them failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub evaluated: usize,
+    pub failed: usize,
+}
+
+impl Summary {
+    /// The exit code for the batch: 1 if any expression failed.
+    pub fn exit_code(self) -> i32 {
+        match self.failed {
+            0 => 0,
+            _ => 1,
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let expressions = match self.evaluated {
+            1 => "expression",
+            _ => "expressions",
+        };
+        match self.failed {
+            0 => write!(f, "{} {} evaluated", self.evaluated, expressions),
+            failed => write!(f, "{} of {} {} failed", failed, self.evaluated, expressions),
+        }
+    }
+}
+
+/// Evaluates every line of `reader` on its own and writes one result per line
+/// to `writer`, carrying on past failures. Blank lines and lines starting with
+/// `#` are skipped. Each line starts from `environment`, so a `let` on one
+/// line doesn't carry over to the next.
+pub fn run(
+    reader: impl BufRead,
+    mut writer: impl Write,
+    format: OutputFormat,
+    options: DisplayOptions,
+    environment: &Environment,
+) -> io::Result<Summary> {
+    let options = options.for_format(format);
+    if format == OutputFormat::Csv {
+        writeln!(writer, "{}", output::CSV_HEADER)?;
+    }
+    let (mut evaluated, mut failed) = (0, 0);
+    for line in reader.lines() {
+        let line = line?;
+        let input = line.trim();
+        if input.is_empty() || input.starts_with('#') {
+            continue;
+        }
+        let result = unit_parser::evaluate_in(input, &mut environment.clone())
+            .map(|result| options.present(result));
+        evaluated += 1;
+        if result.is_err() {
+            failed += 1;
+        }
+        let line = match (&result, format) {
+            (_, OutputFormat::Text) => output::text_line(input, &result, options),
+            (Ok(result), OutputFormat::Json) => output::json_result(input, result).to_string(),
+            (Err(error), OutputFormat::Json) => output::json_error(input, error).to_string(),
+            (_, OutputFormat::Csv) => output::csv_row(input, &result),
+        };
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(Summary { evaluated, failed })
+}
+
+#[cfg(test)]
+mod tests {
+    use unit_parser::{notation::Notation, units::UnitSystem, Environment};
+
+    use super::{run, Summary};
+    use crate::output::{DisplayOptions, OutputFormat};
+
+    const OPTIONS: DisplayOptions = DisplayOptions {
+        system: UnitSystem::SI,
+        best_unit: false,
+        notation: Notation::Plain,
+        precision: None,
+    };
+
+    /// Runs a batch over `input` and returns what it wrote and its summary.
+    fn batch(input: &str, format: OutputFormat) -> (String, Summary) {
+        let mut written = Vec::new();
+        let summary = run(
+            input.as_bytes(),
+            &mut written,
+            format,
+            OPTIONS,
+            &Environment::new(),
+        )
+        .unwrap();
+        (String::from_utf8(written).unwrap(), summary)
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comments_and_counts_failures() {
+        let (written, summary) = batch(
+            "3 m + 2 m\n\n  # a comment\n1 m + 1 s\nlet x = 2; x\nx\n",
+            OutputFormat::Text,
+        );
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "3 m + 2 m => 5 m");
+        assert!(lines[1].starts_with("1 m + 1 s => ERROR: cannot add"));
+        assert_eq!(lines[2], "let x = 2; x => 2 dimensionless");
+        assert_eq!(lines[3], "x => ERROR: unknown variable `x`");
+        assert_eq!(
+            summary,
+            Summary {
+                evaluated: 4,
+                failed: 2
+            }
+        );
+        assert_eq!(summary.exit_code(), 1);
+        assert_eq!(summary.to_string(), "2 of 4 expressions failed");
+    }
+
+    #[test]
+    fn a_clean_batch_exits_with_zero() {
+        let (_, summary) = batch("1 m\n# only this\n", OutputFormat::Text);
+        assert_eq!(summary.exit_code(), 0);
+        assert_eq!(summary.to_string(), "1 expression evaluated");
+        let (_, summary) = batch("1 m\n2 m\n", OutputFormat::Text);
+        assert_eq!(summary.to_string(), "2 expressions evaluated");
+        let (_, summary) = batch("1 m +\n", OutputFormat::Text);
+        assert_eq!(summary.to_string(), "1 of 1 expression failed");
+    }
+
+    #[test]
+    fn writes_one_json_object_per_line() {
+        let (written, _) = batch("2 m * 3 m\n1 m +\n", OutputFormat::Json);
+        let objects: Vec<serde_json::Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0]["magnitude"], 6.0);
+        assert_eq!(objects[0]["unit"], "m^2");
+        assert_eq!(objects[1]["error"]["category"], "parse");
+    }
+
+    #[test]
+    fn writes_csv_with_a_header() {
+        let (written, _) = batch("max(1 m, 2 m)\n1 m + 1 s\n", OutputFormat::Csv);
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines[0], "input,magnitude,unit,kind,error");
+        assert_eq!(lines[1], "\"max(1 m, 2 m)\",2,m,Length,");
+        assert!(lines[2].starts_with("1 m + 1 s,,,,cannot add"));
+    }
+
+    #[test]
+    fn json_and_csv_stay_in_coherent_si() {
+        let options = DisplayOptions {
+            system: UnitSystem::Imperial,
+            best_unit: true,
+            ..OPTIONS
+        };
+        let run = |format| {
+            let mut written = Vec::new();
+            run(
+                "1500000000 W\n3 m\n".as_bytes(),
+                &mut written,
+                format,
+                options,
+                &Environment::new(),
+            )
+            .unwrap();
+            String::from_utf8(written).unwrap()
+        };
+        let json = run(OutputFormat::Json);
+        let power: serde_json::Value = serde_json::from_str(json.lines().next().unwrap()).unwrap();
+        assert_eq!(power["magnitude"], 1.5e9);
+        assert_eq!(power["unit"], "W");
+        let length: serde_json::Value = serde_json::from_str(json.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(length["unit"], "m");
+        assert_eq!(
+            run(OutputFormat::Csv).lines().nth(1),
+            Some("1500000000 W,1500000000,W,Power,")
+        );
+        let text = run(OutputFormat::Text);
+        assert!(text.lines().next().unwrap().ends_with(" hp"));
+    }
+}
@@ -0,0 +1,167 @@
+use std::f64::consts::PI;
+
+use crate::{
+    units::{DIMENSIONLESS, LENGTH, MASS, SPEED, STANDARD_GRAVITY},
+    PhysicalQuantity, PhysicalQuantityBuilder,
+};
+
+/// A named physical constant, which expressions refer to by symbol or alias,
+/// e.g. `h * 500 THz`. Constants are only looked up where a variable could
+/// appear, never after a number, so `c` is the speed of light and not a
+/// dangling centi, and `C` is still the coulomb.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constant {
+    pub symbol: &'static str,
+    pub aliases: &'static [&'static str],
+    pub name: &'static str,
+    pub value: f64,
+    pub physical_quantity: PhysicalQuantity,
+}
+
+const fn constant(
+    symbol: &'static str,
+    aliases: &'static [&'static str],
+    name: &'static str,
+    value: f64,
+    physical_quantity: PhysicalQuantity,
+) -> Constant {
+    Constant {
+        symbol,
+        aliases,
+        name,
+        value,
+        physical_quantity,
+    }
+}
+
+const ACCELERATION: PhysicalQuantity = PhysicalQuantityBuilder::new().time(-2).length(1).build();
+const ACTION: PhysicalQuantity = PhysicalQuantityBuilder::new()
+    .time(-1)
+    .length(2)
+    .mass(1)
+    .build();
+const ENTROPY: PhysicalQuantity = PhysicalQuantityBuilder::new()
+    .time(-2)
+    .length(2)
+    .mass(1)
+    .temperature(-1)
+    .build();
+
+const PLANCK: f64 = 6.626_070_15e-34;
+const BOLTZMANN: f64 = 1.380_649e-23;
+const AVOGADRO: f64 = 6.022_140_76e23;
+
+/// The defining constants of the SI, which are exact, and other constants at
+/// their CODATA 2018 recommended values.
+pub static CONSTANTS: &[Constant] = &[
+    constant("c", &[], "speed of light in vacuum", 299_792_458.0, SPEED),
+    constant("h", &[], "Planck constant", PLANCK, ACTION),
+    constant(
+        "ħ",
+        &["hbar"],
+        "reduced Planck constant",
+        PLANCK / (2.0 * PI),
+        ACTION,
+    ),
+    constant("k_B", &["kB"], "Boltzmann constant", BOLTZMANN, ENTROPY),
+    constant(
+        "N_A",
+        &["NA"],
+        "Avogadro constant",
+        AVOGADRO,
+        PhysicalQuantityBuilder::new()
+            .amount_of_substance(-1)
+            .build(),
+    ),
+    constant(
+        "e",
+        &[],
+        "elementary charge",
+        1.602_176_634e-19,
+        PhysicalQuantityBuilder::new().time(1).current(1).build(),
+    ),
+    constant(
+        "R",
+        &[],
+        "molar gas constant",
+        BOLTZMANN * AVOGADRO,
+        PhysicalQuantityBuilder::new()
+            .time(-2)
+            .length(2)
+            .mass(1)
+            .temperature(-1)
+            .amount_of_substance(-1)
+            .build(),
+    ),
+    constant(
+        "G",
+        &[],
+        "Newtonian constant of gravitation",
+        6.674_30e-11,
+        PhysicalQuantityBuilder::new()
+            .time(-2)
+            .length(3)
+            .mass(-1)
+            .build(),
+    ),
+    constant(
+        "g₀",
+        &["g0", "g_0"],
+        "standard acceleration of gravity",
+        STANDARD_GRAVITY,
+        ACCELERATION,
+    ),
+    constant(
+        "ε₀",
+        &["eps0", "epsilon_0"],
+        "vacuum electric permittivity",
+        8.854_187_812_8e-12,
+        PhysicalQuantityBuilder::new()
+            .time(4)
+            .length(-3)
+            .mass(-1)
+            .current(2)
+            .build(),
+    ),
+    constant(
+        "μ₀",
+        &["µ₀", "mu0", "mu_0"],
+        "vacuum magnetic permeability",
+        1.256_637_062_12e-6,
+        PhysicalQuantityBuilder::new()
+            .time(-2)
+            .length(1)
+            .mass(1)
+            .current(-2)
+            .build(),
+    ),
+    constant("m_e", &[], "electron mass", 9.109_383_701_5e-31, MASS),
+    constant("m_p", &[], "proton mass", 1.672_621_923_69e-27, MASS),
+    constant("m_n", &[], "neutron mass", 1.674_927_498_04e-27, MASS),
+    constant("a₀", &["a0"], "Bohr radius", 5.291_772_109_03e-11, LENGTH),
+    constant(
+        "σ",
+        &["sigma"],
+        "Stefan-Boltzmann constant",
+        5.670_374_419e-8,
+        PhysicalQuantityBuilder::new()
+            .time(-3)
+            .mass(1)
+            .temperature(-4)
+            .build(),
+    ),
+    constant(
+        "α",
+        &["alpha"],
+        "fine-structure constant",
+        7.297_352_569_3e-3,
+        DIMENSIONLESS,
+    ),
+];
+
+/// Finds the constant with exactly this symbol or alias.
+pub fn lookup(symbol: &str) -> Option<&'static Constant> {
+    CONSTANTS
+        .iter()
+        .find(|constant| constant.symbol == symbol || constant.aliases.contains(&symbol))
+}
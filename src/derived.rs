@@ -0,0 +1,162 @@
+use crate::{
+    rational::Rational,
+    units::{self, Unit},
+    PhysicalQuantity,
+};
+
+/// The named coherent units that quantities are written in, most preferred
+/// first. Where two ways of writing a quantity are equally simple, the one
+/// using the earlier unit wins, so surface tension is `N/m` rather than
+/// `Pa m`.
+pub static DERIVED_UNITS: &[&str] = &[
+    "W", "J", "N", "Pa", "V", "C", "Ω", "S", "F", "H", "Wb", "T", "Hz", "kat", "sr",
+];
+
+/// Reads the exponent of one dimension of a physical quantity.
+type Exponent = fn(PhysicalQuantity) -> Rational;
+
+/// The coherent SI base units in the order they are written, each with the
+/// exponent of its dimension.
+const BASE_UNITS: [(&str, Exponent); 8] = [
+    ("kg", PhysicalQuantity::mass),
+    ("m", PhysicalQuantity::length),
+    ("s", PhysicalQuantity::time),
+    ("A", PhysicalQuantity::current),
+    ("K", PhysicalQuantity::temperature),
+    ("mol", PhysicalQuantity::amount_of_substance),
+    ("cd", PhysicalQuantity::luminous_intensity),
+    ("rad", PhysicalQuantity::angle),
+];
+
+/// The absolute value of an exponent, or `None` if it is out of range.
+fn abs(exponent: Rational) -> Option<Rational> {
+    match exponent < Rational::ZERO {
+        true => Rational::ZERO.checked_sub(exponent),
+        false => Some(exponent),
+    }
+}
+
+/// How many units it takes to write `physical_quantity` in base units,
+/// counted with their powers, or `None` if that is out of range.
+fn base_cost(physical_quantity: PhysicalQuantity) -> Option<Rational> {
+    BASE_UNITS
+        .iter()
+        .try_fold(Rational::ZERO, |total, (_, exponent)| {
+            total.checked_add(abs(exponent(physical_quantity))?)
+        })
+}
+
+/// `physical_quantity` written in base units alone.
+fn base_factors(physical_quantity: PhysicalQuantity) -> Vec<(&'static str, Rational)> {
+    BASE_UNITS
+        .iter()
+        .filter(|(_, exponent)| exponent(physical_quantity) != 0)
+        .map(|(symbol, exponent)| (*symbol, exponent(physical_quantity)))
+        .collect()
+}
+
+/// Whether `physical_quantity` only involves length, time and angle, like a
+/// speed or an acceleration.
+fn is_kinematic(physical_quantity: PhysicalQuantity) -> bool {
+    physical_quantity.mass() == 0
+        && physical_quantity.current() == 0
+        && physical_quantity.temperature() == 0
+        && physical_quantity.amount_of_substance() == 0
+        && physical_quantity.luminous_intensity() == 0
+}
+
+/// Writes `physical_quantity` as the simplest product of at most two of the
+/// `preferred` units and the base units, as symbols and their exponents.
+/// Preferred symbols that aren't coherent units are ignored.
+///
+/// The simplest product has the fewest units counted with their powers, so
+/// momentum is `N s` and not `kg m s^-1`. Whatever two preferred units cancel
+/// out between them counts too, so current density is `A/m^2` rather than
+/// `T/H`, and charge density `C/m^3` rather than `Pa/V`. Ties go to the
+/// product with fewer distinct units, then fewer preferred units in the
+/// denominator (`S/m` rather than `Ω^-1 m^-1`), then fewer preferred units,
+/// then the more preferred.
+///
+/// A preferred unit that is no simpler than its base units, like `Hz`, is only
+/// used on its own, and kinematic quantities are always written in base
+/// units, so acceleration is `m/s^2` rather than `N/kg`. So is any quantity
+/// with exponents too large to compare ways of writing it.
+pub fn decompose(
+    physical_quantity: PhysicalQuantity,
+    preferred: &[&str],
+) -> Vec<(&'static str, Rational)> {
+    let named: Vec<&'static Unit> = preferred
+        .iter()
+        .filter_map(|symbol| units::lookup(symbol))
+        .filter(|unit| unit.factor == 1.0 && unit.offset == 0.0)
+        .collect();
+    if let Some(unit) = named
+        .iter()
+        .find(|unit| unit.physical_quantity == physical_quantity)
+    {
+        return vec![(unit.symbol, Rational::ONE)];
+    }
+    let named: Vec<&'static Unit> = match is_kinematic(physical_quantity) {
+        true => vec![],
+        false => named
+            .into_iter()
+            .filter(|unit| {
+                base_cost(unit.physical_quantity).is_some_and(|cost| cost > Rational::ONE)
+            })
+            .collect(),
+    };
+
+    let powers = [-2, -1, 1, 2].map(Rational::integer);
+    let mut candidates: Vec<Vec<(&Unit, Rational)>> = vec![vec![]];
+    for (i, first) in named.iter().enumerate() {
+        for first_power in powers {
+            candidates.push(vec![(first, first_power)]);
+            for second in &named[i + 1..] {
+                for second_power in powers {
+                    candidates.push(vec![(first, first_power), (second, second_power)]);
+                }
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let remainder =
+                candidate
+                    .iter()
+                    .try_fold(physical_quantity, |remainder, (unit, power)| {
+                        remainder.checked_div(unit.physical_quantity.checked_pow(*power)?)
+                    })?;
+            let mut cost = base_cost(remainder)?;
+            for (_, power) in &candidate {
+                cost = cost.checked_add(abs(*power)?)?;
+            }
+            if let [(first, first_power), (second, second_power)] = candidate[..] {
+                let first = first.physical_quantity.checked_pow(first_power)?;
+                let second = second.physical_quantity.checked_pow(second_power)?;
+                for (_, exponent) in BASE_UNITS {
+                    let (first, second) = (exponent(first), exponent(second));
+                    // Opposite signs cancel by the smaller of the two.
+                    if (first < Rational::ZERO) != (second < Rational::ZERO) {
+                        cost = cost.checked_add(abs(first)?.min(abs(second)?))?;
+                    }
+                }
+            }
+            let mut factors: Vec<(&'static str, Rational)> = candidate
+                .iter()
+                .map(|(unit, power)| (unit.symbol, *power))
+                .collect();
+            factors.extend(base_factors(remainder));
+            let inverted = candidate
+                .iter()
+                .filter(|(_, power)| *power < Rational::ZERO)
+                .count();
+            let score = (cost, factors.len(), inverted, candidate.len());
+            Some((score, factors))
+        })
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, factors)| factors)
+        // Exponents too large to count are written in base units as they are.
+        .unwrap_or_else(|| base_factors(physical_quantity))
+}
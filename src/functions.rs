@@ -0,0 +1,185 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    rational::Rational,
+    units::{ANGLE, DIMENSIONLESS},
+    ConcreteNumber, CustomError,
+};
+
+/// A function that can be called in an expression, e.g. `sqrt(2 g h)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sqrt,
+    Cbrt,
+    Abs,
+    Min,
+    Max,
+    Round,
+    Floor,
+    Ceil,
+    Exp,
+    Ln,
+    Log10,
+    Sin,
+    Cos,
+    Tan,
+    Atan2,
+    Hypot,
+}
+
+pub static FUNCTIONS: &[Function] = &[
+    Function::Sqrt,
+    Function::Cbrt,
+    Function::Abs,
+    Function::Min,
+    Function::Max,
+    Function::Round,
+    Function::Floor,
+    Function::Ceil,
+    Function::Exp,
+    Function::Ln,
+    Function::Log10,
+    Function::Sin,
+    Function::Cos,
+    Function::Tan,
+    Function::Atan2,
+    Function::Hypot,
+];
+
+/// Finds the function with this name.
+pub fn lookup(name: &str) -> Option<Function> {
+    FUNCTIONS
+        .iter()
+        .copied()
+        .find(|function| function.name() == name)
+}
+
+impl Function {
+    pub fn name(self) -> &'static str {
+        match self {
+            Function::Sqrt => "sqrt",
+            Function::Cbrt => "cbrt",
+            Function::Abs => "abs",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Round => "round",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+            Function::Log10 => "log10",
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Atan2 => "atan2",
+            Function::Hypot => "hypot",
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Function::Min | Function::Max | Function::Atan2 | Function::Hypot => 2,
+            _ => 1,
+        }
+    }
+
+    /// Applies the function to already evaluated arguments.
+    ///
+    /// Roots take roots of the dimensions too. `abs`, `round`, `floor` and
+    /// `ceil` keep the dimension and work on the magnitude in coherent SI.
+    /// `min`, `max`, `atan2` and `hypot` need two arguments of the same
+    /// dimension. The transcendental functions need dimensionless arguments,
+    /// except that trigonometric functions also take angles, and `atan2`
+    /// gives an angle.
+    pub fn apply(self, arguments: Vec<ConcreteNumber>) -> Result<ConcreteNumber, CustomError> {
+        if arguments.len() != self.arity() {
+            return Err(CustomError::WrongNumberOfArguments {
+                function: self,
+                expected: self.arity(),
+                found: arguments.len(),
+            });
+        }
+        let mut arguments = arguments.into_iter();
+        let x = arguments.next().unwrap();
+        match self {
+            Function::Sqrt => return x.pow(Rational::new(1, 2)),
+            Function::Cbrt => return x.pow(Rational::new(1, 3)),
+            Function::Min | Function::Max | Function::Atan2 | Function::Hypot => {
+                let y = arguments.next().unwrap();
+                return self.apply_binary(x, y);
+            }
+            _ => {}
+        }
+        if x.is_absolute_temperature() {
+            return Err(CustomError::FunctionOfAbsoluteTemperature {
+                function: self,
+                temperature: Box::new(x),
+            });
+        }
+        let magnitude = x.magnitude();
+        match self {
+            Function::Abs => Ok(ConcreteNumber::new(magnitude.abs(), x.physical_quantity())),
+            Function::Round => Ok(ConcreteNumber::new(
+                magnitude.round(),
+                x.physical_quantity(),
+            )),
+            Function::Floor => Ok(ConcreteNumber::new(
+                magnitude.floor(),
+                x.physical_quantity(),
+            )),
+            Function::Ceil => Ok(ConcreteNumber::new(magnitude.ceil(), x.physical_quantity())),
+            _ => {
+                self.require_dimensionless(&x)?;
+                let value = match self {
+                    Function::Exp => magnitude.exp(),
+                    Function::Ln => magnitude.ln(),
+                    Function::Log10 => magnitude.log10(),
+                    Function::Sin => magnitude.sin(),
+                    Function::Cos => magnitude.cos(),
+                    _ => magnitude.tan(),
+                };
+                Ok(ConcreteNumber::from(value))
+            }
+        }
+    }
+
+    fn apply_binary(
+        self,
+        x: ConcreteNumber,
+        y: ConcreteNumber,
+    ) -> Result<ConcreteNumber, CustomError> {
+        if x.physical_quantity() != y.physical_quantity() {
+            return Err(CustomError::MismatchedArguments {
+                function: self,
+                lhs: x.physical_quantity(),
+                rhs: y.physical_quantity(),
+            });
+        }
+        Ok(match self {
+            Function::Min if y.magnitude() < x.magnitude() => y,
+            Function::Max if y.magnitude() > x.magnitude() => y,
+            Function::Min | Function::Max => x,
+            Function::Atan2 => ConcreteNumber::new(x.magnitude().atan2(y.magnitude()), ANGLE),
+            _ => ConcreteNumber::new(x.magnitude().hypot(y.magnitude()), x.physical_quantity()),
+        })
+    }
+
+    fn require_dimensionless(self, x: &ConcreteNumber) -> Result<(), CustomError> {
+        let angle_allowed = matches!(self, Function::Sin | Function::Cos | Function::Tan);
+        if x.physical_quantity() == DIMENSIONLESS || angle_allowed && x.physical_quantity() == ANGLE
+        {
+            Ok(())
+        } else {
+            Err(CustomError::DimensionedArgument {
+                function: self,
+                physical_quantity: x.physical_quantity(),
+            })
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,2175 @@
+//! Parses and evaluates expressions over physical quantities, such as
+//! `3 km / 20 min to km/h`, checking their dimensions as it goes.
+//!
+//! [`evaluate`] is the usual entry point; [`parse`] gives the expression tree
+//! without evaluating it.
+
+pub mod constants;
+pub mod derived;
+pub mod functions;
+pub mod notation;
+mod parser;
+pub mod prefix;
+pub mod rational;
+pub mod units;
+
+pub use parser::{Expr, ExprKind};
+
+use functions::Function;
+use notation::{Notation, NotationDisplay};
+use rational::Rational;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display, LowerExp, UpperExp},
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct PhysicalQuantity {
+    time: Rational,
+    length: Rational,
+    mass: Rational,
+    current: Rational,
+    temperature: Rational,
+    amount_of_substance: Rational,
+    luminous_intensity: Rational,
+    /// Plane angle, which the SI counts as dimensionless. It is only kept
+    /// when angles are tracked (see [`Environment::set_track_angles`]), and
+    /// solid angle is angle squared.
+    angle: Rational,
+}
+
+impl PhysicalQuantity {
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new(
+        time: Rational,
+        length: Rational,
+        mass: Rational,
+        current: Rational,
+        temperature: Rational,
+        amount_of_substance: Rational,
+        luminous_intensity: Rational,
+        angle: Rational,
+    ) -> Self {
+        Self {
+            time,
+            length,
+            mass,
+            current,
+            temperature,
+            amount_of_substance,
+            luminous_intensity,
+            angle,
+        }
+    }
+
+    pub const fn time(self) -> Rational {
+        self.time
+    }
+
+    pub const fn length(self) -> Rational {
+        self.length
+    }
+
+    pub const fn mass(self) -> Rational {
+        self.mass
+    }
+
+    pub const fn current(self) -> Rational {
+        self.current
+    }
+
+    pub const fn temperature(self) -> Rational {
+        self.temperature
+    }
+
+    pub const fn amount_of_substance(self) -> Rational {
+        self.amount_of_substance
+    }
+
+    pub const fn luminous_intensity(self) -> Rational {
+        self.luminous_intensity
+    }
+
+    pub const fn angle(self) -> Rational {
+        self.angle
+    }
+
+    /// The same quantity with angles counted as dimensionless, as the SI
+    /// counts them, so `rad/s` becomes `Hz`.
+    pub const fn without_angle(self) -> PhysicalQuantity {
+        PhysicalQuantity {
+            angle: Rational::ZERO,
+            ..self
+        }
+    }
+
+    /// Raises every dimension to `exponent`, e.g. length to area for 2, or
+    /// area to length for 1/2. Panics if an exponent would be out of range;
+    /// see [`PhysicalQuantity::checked_pow`].
+    pub fn pow(self, exponent: Rational) -> PhysicalQuantity {
+        PhysicalQuantity::new(
+            self.time * exponent,
+            self.length * exponent,
+            self.mass * exponent,
+            self.current * exponent,
+            self.temperature * exponent,
+            self.amount_of_substance * exponent,
+            self.luminous_intensity * exponent,
+            self.angle * exponent,
+        )
+    }
+
+    /// Combines the exponents of two quantities dimension by dimension, or
+    /// returns `None` if any of them is out of range.
+    fn zip_exponents(
+        self,
+        rhs: PhysicalQuantity,
+        combine: impl Fn(Rational, Rational) -> Option<Rational>,
+    ) -> Option<PhysicalQuantity> {
+        Some(PhysicalQuantity::new(
+            combine(self.time, rhs.time)?,
+            combine(self.length, rhs.length)?,
+            combine(self.mass, rhs.mass)?,
+            combine(self.current, rhs.current)?,
+            combine(self.temperature, rhs.temperature)?,
+            combine(self.amount_of_substance, rhs.amount_of_substance)?,
+            combine(self.luminous_intensity, rhs.luminous_intensity)?,
+            combine(self.angle, rhs.angle)?,
+        ))
+    }
+
+    /// The product of two quantities, or `None` if an exponent would be out
+    /// of range.
+    pub fn checked_mul(self, rhs: PhysicalQuantity) -> Option<PhysicalQuantity> {
+        self.zip_exponents(rhs, Rational::checked_add)
+    }
+
+    /// The quotient of two quantities, or `None` if an exponent would be out
+    /// of range.
+    pub fn checked_div(self, rhs: PhysicalQuantity) -> Option<PhysicalQuantity> {
+        self.zip_exponents(rhs, Rational::checked_sub)
+    }
+
+    /// Raises every dimension to `exponent`, or returns `None` if an exponent
+    /// would be out of range.
+    pub fn checked_pow(self, exponent: Rational) -> Option<PhysicalQuantity> {
+        self.zip_exponents(self, |exponent_of_self, _| {
+            exponent_of_self.checked_mul(exponent)
+        })
+    }
+
+    /// The exponents as integers, if none of them is fractional.
+    #[allow(clippy::type_complexity)]
+    fn integer_exponents(self) -> Option<(i16, i16, i16, i16, i16, i16, i16, i16)> {
+        let exponents = [
+            self.time,
+            self.length,
+            self.mass,
+            self.current,
+            self.temperature,
+            self.amount_of_substance,
+            self.luminous_intensity,
+            self.angle,
+        ];
+        if exponents.iter().all(|exponent| exponent.is_integer()) {
+            let [t, l, m, i, th, n, j, a] = exponents.map(Rational::numerator);
+            Some((t, l, m, i, th, n, j, a))
+        } else {
+            None
+        }
+    }
+
+    pub fn dimensions(self) -> String {
+        format!("Dimension({})", self.kind())
+    }
+
+    /// The name of the kind of quantity this is, e.g. `Force`, or its base
+    /// dimensions when it has no name, e.g. `Length^2 Time^-1`.
+    pub fn kind(self) -> String {
+        match self.integer_exponents() {
+            Some((0, 0, 0, 0, 0, 0, 0, 0)) => "Dimensionless".to_owned(),
+            Some((1, 0, 0, 0, 0, 0, 0, 0)) => "Time".to_owned(), // Second (s)
+            Some((0, 1, 0, 0, 0, 0, 0, 0)) => "Length".to_owned(), // Metre (m)
+            Some((0, 0, 1, 0, 0, 0, 0, 0)) => "Mass".to_owned(), // Kilogram (kg)
+            Some((0, 0, 0, 1, 0, 0, 0, 0)) => "Current".to_owned(), // Ampere (A)
+            Some((0, 0, 0, 0, 1, 0, 0, 0)) => "Temperature".to_owned(), //  Kelvin (K)
+            Some((0, 0, 0, 0, 0, 1, 0, 0)) => "AmountOfSubstance".to_owned(), // Mole (mol)
+            Some((0, 0, 0, 0, 0, 0, 1, 0)) => "LuminousIntensity".to_owned(), // Candela (cd)
+            Some((-1, 0, 0, 0, 0, 0, 0, 0)) => "Frequency".to_owned(), // Hertz (Hz) = second^-1
+            Some((-2, 1, 1, 0, 0, 0, 0, 0)) => "Force".to_owned(), // Newton (N)= kg * m * s^-2
+            Some((-2, -1, 1, 0, 0, 0, 0, 0)) => "Pressure".to_owned(), // Pascal (Pa)= N * m^-2 = kg * m^-1 * s^-2
+            Some((-2, 2, 1, 0, 0, 0, 0, 0)) => "Energy".to_owned(), // Joule (J) = N * m = kg * m^2 * s^-2
+            Some((-3, 2, 1, 0, 0, 0, 0, 0)) => "Power".to_owned(), // Power (W) = J * s^-1 = kg * m^2 * s^-3
+            Some((1, 0, 0, 1, 0, 0, 0, 0)) => "ElectricCharge".to_owned(), // Coulomb (C) = A * s
+            Some((-3, 2, 1, -1, 0, 0, 0, 0)) => "ElectricPotential".to_owned(), // Volt (V) = J * C^-1 = kg * m^2 * s^-3 * A^-1
+            Some((-2, 2, 1, -1, 0, 0, 0, 0)) => "MagneticFlux".to_owned(), // Weber (Wb) = V * s = kg * m^2 * s^-2 * A^-1
+            Some((-2, 0, 1, -1, 0, 0, 0, 0)) => "MagneticFluxDensity".to_owned(), // Tesla (T) = Wb * m^-2 = kg * s^-2 * A^-1
+            Some((4, -2, -1, 2, 0, 0, 0, 0)) => "ElectricalCapacitance".to_owned(), // Farad (F) = kg^-1 * m^-2 * s^4 * A^2
+            Some((-3, 2, 1, -2, 0, 0, 0, 0)) => "ElectricalResistance".to_owned(), // Ohm (Ω) = kg * m^2 * s^−3 * A^−2
+            Some((3, -2, -1, 2, 0, 0, 0, 0)) => "ElectricalConductance".to_owned(), // Siemens (S) = kg^−1 * m^−2 * s^3 * A^2
+            Some((-2, 2, 1, -2, 0, 0, 0, 0)) => "ElectricalInductance".to_owned(), // Henry (H) = kg * m^2 * s^−2 * A^−2
+            Some((1, 0, 0, 0, 0, 1, 0, 0)) => "CatalyticActivity".to_owned(), // Katal (kat) = mol * s^-1
+            Some((0, 2, 0, 0, 0, 0, 0, 0)) => "Area".to_owned(),
+            Some((0, 3, 0, 0, 0, 0, 0, 0)) => "Volume".to_owned(),
+            Some((-1, 1, 0, 0, 0, 0, 0, 0)) => "Speed".to_owned(),
+            Some((-2, 1, 0, 0, 0, 0, 0, 0)) => "Acceleration".to_owned(),
+            Some((-1, 3, 0, 0, 0, 0, 0, 0)) => "VolumetricFlow".to_owned(),
+            Some((-1, 1, 1, 0, 0, 0, 0, 0)) => "Momentum".to_owned(),
+            Some((0, 0, 0, 0, 0, 0, 0, 1)) => "Angle".to_owned(), // Radian (rad)
+            Some((0, 0, 0, 0, 0, 0, 0, 2)) => "SolidAngle".to_owned(), // Steradian (sr) = rad^2
+            Some((-1, 0, 0, 0, 0, 0, 0, 1)) => "AngularVelocity".to_owned(),
+            Some((-2, 0, 0, 0, 0, 0, 0, 1)) => "AngularAcceleration".to_owned(),
+            _ => {
+                let mut units: Vec<(&str, Rational)> = vec![];
+                if self.time != 0 {
+                    units.push(("Time", self.time));
+                }
+                if self.length != 0 {
+                    units.push(("Length", self.length));
+                }
+                if self.mass != 0 {
+                    units.push(("Mass", self.mass));
+                }
+                if self.current != 0 {
+                    units.push(("Current", self.current));
+                }
+                if self.temperature != 0 {
+                    units.push(("Temperature", self.temperature));
+                }
+                if self.amount_of_substance != 0 {
+                    units.push(("AmountOfSubstance", self.amount_of_substance));
+                }
+                if self.luminous_intensity != 0 {
+                    units.push(("LuminousIntensity", self.luminous_intensity));
+                }
+                if self.angle != 0 {
+                    units.push(("Angle", self.angle));
+                }
+                units.sort_by(|a, b| a.0.cmp(b.0));
+                let concatenated: Vec<String> = units
+                    .into_iter()
+                    .map(|(unit, exponent)| match exponent {
+                        exponent if exponent == 1 => unit.to_string(),
+                        exponent if exponent.is_integer() => format!("{unit}^{exponent}"),
+                        _ => format!("{unit}^({exponent})"),
+                    })
+                    .collect();
+                concatenated.join(" ")
+            }
+        }
+    }
+}
+
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// Writes an exponent in Unicode superscripts, e.g. `-2` as `⁻²` and `1/2`
+/// as `¹ᐟ²`.
+fn superscript(exponent: Rational) -> String {
+    exponent
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            '-' => '⁻',
+            '/' => 'ᐟ',
+            digit => SUPERSCRIPT_DIGITS[digit.to_digit(10).unwrap() as usize],
+        })
+        .collect()
+}
+
+/// Writes a unit as a product of powers, with any negative powers after a `/`
+/// (`J/(kg K)`), or as negative powers when there is nothing to divide
+/// (`s^-2`). The alternate form writes the powers as superscripts.
+fn write_factors(
+    f: &mut std::fmt::Formatter<'_>,
+    factors: &[(&str, Rational)],
+) -> std::fmt::Result {
+    let alternate = f.alternate();
+    let factor = |(unit, exponent): &(&str, Rational)| match *exponent {
+        exponent if exponent == 1 => unit.to_string(),
+        exponent if alternate => format!("{unit}{}", superscript(exponent)),
+        exponent if exponent.is_integer() => format!("{unit}^{exponent}"),
+        exponent => format!("{unit}^({exponent})"),
+    };
+    let (numerator, denominator): (Vec<_>, Vec<_>) = factors
+        .iter()
+        .copied()
+        .partition(|(_, exponent)| *exponent > Rational::ZERO);
+    let denominator: Option<Vec<(&str, Rational)>> = denominator
+        .into_iter()
+        .map(|(unit, exponent)| Some((unit, Rational::ZERO.checked_sub(exponent)?)))
+        .collect();
+    let join = |factors: &[(&str, Rational)]| -> String {
+        factors.iter().map(factor).collect::<Vec<_>>().join(" ")
+    };
+    // A power too large to negate stays negative, like a bare denominator.
+    let Some(denominator) = denominator else {
+        return write!(f, "{}", join(factors));
+    };
+    match (numerator.is_empty(), denominator.len()) {
+        (true, _) => write!(f, "{}", join(factors)),
+        (false, 0) => write!(f, "{}", join(factors)),
+        (false, 1) => write!(f, "{}/{}", join(&numerator), join(&denominator)),
+        (false, _) => write!(f, "{}/({})", join(&numerator), join(&denominator)),
+    }
+}
+
+/// A physical quantity written in terms of a chosen list of named units; see
+/// [`PhysicalQuantity::with_units`].
+#[derive(Debug, Clone, Copy)]
+pub struct UnitDisplay<'a> {
+    physical_quantity: PhysicalQuantity,
+    preferred: &'a [&'a str],
+}
+
+impl Display for UnitDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.physical_quantity == PhysicalQuantityBuilder::new().build() {
+            return write!(f, "dimensionless");
+        }
+        write_factors(
+            f,
+            &derived::decompose(self.physical_quantity, self.preferred),
+        )
+    }
+}
+
+impl PhysicalQuantity {
+    /// Writes the quantity as the simplest product of the `preferred` named
+    /// units and the base units, e.g. `N s` for momentum, with the earlier
+    /// units in `preferred` winning ties. `Display` uses
+    /// [`derived::DERIVED_UNITS`].
+    pub fn with_units<'a>(self, preferred: &'a [&'a str]) -> UnitDisplay<'a> {
+        UnitDisplay {
+            physical_quantity: self,
+            preferred,
+        }
+    }
+}
+
+/// Writes the coherent SI unit for this quantity, using named derived units
+/// where they make it simpler. The alternate form (`{:#}`) writes exponents as
+/// superscripts, e.g. `m/s²` rather than `m/s^2`.
+impl Display for PhysicalQuantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.with_units(derived::DERIVED_UNITS), f)
+    }
+}
+
+impl Mul for PhysicalQuantity {
+    type Output = PhysicalQuantity;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        PhysicalQuantity::new(
+            self.time + rhs.time,
+            self.length + rhs.length,
+            self.mass + rhs.mass,
+            self.current + rhs.current,
+            self.temperature + rhs.temperature,
+            self.amount_of_substance + rhs.amount_of_substance,
+            self.luminous_intensity + rhs.luminous_intensity,
+            self.angle + rhs.angle,
+        )
+    }
+}
+
+impl Div for PhysicalQuantity {
+    type Output = PhysicalQuantity;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        PhysicalQuantity::new(
+            self.time - rhs.time,
+            self.length - rhs.length,
+            self.mass - rhs.mass,
+            self.current - rhs.current,
+            self.temperature - rhs.temperature,
+            self.amount_of_substance - rhs.amount_of_substance,
+            self.luminous_intensity - rhs.luminous_intensity,
+            self.angle - rhs.angle,
+        )
+    }
+}
+
+/// Builds a [`PhysicalQuantity`] with integer exponents. They are `i16`s,
+/// the range a [`Rational`] exponent can hold.
+#[derive(Default)]
+pub struct PhysicalQuantityBuilder {
+    time: i16,
+    length: i16,
+    mass: i16,
+    current: i16,
+    temperature: i16,
+    amount_of_substance: i16,
+    luminous_intensity: i16,
+    angle: i16,
+}
+
+impl PhysicalQuantityBuilder {
+    pub const fn new() -> Self {
+        PhysicalQuantityBuilder {
+            time: 0,
+            length: 0,
+            mass: 0,
+            current: 0,
+            temperature: 0,
+            amount_of_substance: 0,
+            luminous_intensity: 0,
+            angle: 0,
+        }
+    }
+
+    pub const fn time(mut self, time: i16) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub const fn length(mut self, length: i16) -> Self {
+        self.length = length;
+        self
+    }
+
+    pub const fn mass(mut self, mass: i16) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    pub const fn current(mut self, current: i16) -> Self {
+        self.current = current;
+        self
+    }
+
+    pub const fn temperature(mut self, temperature: i16) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub const fn amount_of_substance(mut self, amount_of_substance: i16) -> Self {
+        self.amount_of_substance = amount_of_substance;
+        self
+    }
+
+    pub const fn luminous_intensity(mut self, luminous_intensity: i16) -> Self {
+        self.luminous_intensity = luminous_intensity;
+        self
+    }
+
+    pub const fn angle(mut self, angle: i16) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub const fn build(self) -> PhysicalQuantity {
+        PhysicalQuantity {
+            time: Rational::integer(self.time),
+            length: Rational::integer(self.length),
+            mass: Rational::integer(self.mass),
+            current: Rational::integer(self.current),
+            temperature: Rational::integer(self.temperature),
+            amount_of_substance: Rational::integer(self.amount_of_substance),
+            luminous_intensity: Rational::integer(self.luminous_intensity),
+            angle: Rational::integer(self.angle),
+        }
+    }
+}
+
+impl Mul for PhysicalQuantityBuilder {
+    type Output = PhysicalQuantityBuilder;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let product: PhysicalQuantityBuilder = PhysicalQuantityBuilder::new();
+        product
+            .time(self.time + rhs.time)
+            .length(self.length + rhs.length)
+            .mass(self.mass + rhs.mass)
+            .current(self.current + rhs.current)
+            .temperature(self.temperature + rhs.temperature)
+            .amount_of_substance(self.amount_of_substance + rhs.amount_of_substance)
+            .luminous_intensity(self.luminous_intensity + rhs.luminous_intensity)
+            .angle(self.angle + rhs.angle)
+    }
+}
+
+impl Div for PhysicalQuantityBuilder {
+    type Output = PhysicalQuantityBuilder;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let quotient: PhysicalQuantityBuilder = PhysicalQuantityBuilder::new();
+        quotient
+            .time(self.time - rhs.time)
+            .length(self.length - rhs.length)
+            .mass(self.mass - rhs.mass)
+            .current(self.current - rhs.current)
+            .temperature(self.temperature - rhs.temperature)
+            .amount_of_substance(self.amount_of_substance - rhs.amount_of_substance)
+            .luminous_intensity(self.luminous_intensity - rhs.luminous_intensity)
+            .angle(self.angle - rhs.angle)
+    }
+}
+
+/// A unit a `ConcreteNumber` has been converted to for display, such as the
+/// `km/h` in `30 m/s to km/h`. `scale` is the size of one of this unit in
+/// coherent SI, and `offset` is where an affine temperature scale puts its
+/// zero, so that `kelvin = (value + offset) * scale`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayUnit {
+    symbol: String,
+    scale: f64,
+    offset: f64,
+    physical_quantity: PhysicalQuantity,
+}
+
+impl DisplayUnit {
+    pub fn new(symbol: &str, scale: f64, offset: f64, physical_quantity: PhysicalQuantity) -> Self {
+        Self {
+            symbol: symbol.to_owned(),
+            scale,
+            offset,
+            physical_quantity,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    pub fn physical_quantity(&self) -> PhysicalQuantity {
+        self.physical_quantity
+    }
+
+    pub fn is_affine(&self) -> bool {
+        self.offset != 0.0
+    }
+
+    /// The same unit with angles counted as dimensionless.
+    pub fn without_angle(self) -> DisplayUnit {
+        DisplayUnit {
+            physical_quantity: self.physical_quantity.without_angle(),
+            ..self
+        }
+    }
+}
+
+/// A magnitude in coherent SI units together with its physical quantity. The
+/// optional display unit only affects how the number is printed.
+///
+/// Temperatures read on an affine scale such as `20 °C` are absolute: they
+/// are points on the scale rather than differences between points, so they
+/// can only have a difference added to or taken from them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConcreteNumber {
+    magnitude: f64,
+    physical_quantity: PhysicalQuantity,
+    display_unit: Option<DisplayUnit>,
+    absolute_temperature: bool,
+}
+
+impl ConcreteNumber {
+    pub fn new(quantity: f64, unit: PhysicalQuantity) -> Self {
+        Self {
+            magnitude: quantity,
+            physical_quantity: unit,
+            display_unit: None,
+            absolute_temperature: false,
+        }
+    }
+
+    /// The magnitude in coherent SI units, whatever unit it is displayed in.
+    pub fn magnitude(&self) -> f64 {
+        self.magnitude
+    }
+
+    pub fn physical_quantity(&self) -> PhysicalQuantity {
+        self.physical_quantity
+    }
+
+    pub fn display_unit(&self) -> Option<&DisplayUnit> {
+        self.display_unit.as_ref()
+    }
+
+    /// The magnitude as it is shown, in the display unit if there is one.
+    pub fn displayed_magnitude(&self) -> f64 {
+        match &self.display_unit {
+            // A scale such as 1e-3 can't be held exactly, but its reciprocal
+            // can, so `0.3 m` is exactly `300 mm` rather than just under.
+            Some(unit) if (1.0 / unit.scale).fract() == 0.0 => {
+                self.magnitude * (1.0 / unit.scale) - unit.offset
+            }
+            Some(unit) => self.magnitude / unit.scale - unit.offset,
+            None => self.magnitude,
+        }
+    }
+
+    /// The unit the number is shown in: its display unit, or else the
+    /// coherent SI unit for its physical quantity.
+    pub fn unit_symbol(&self) -> String {
+        match &self.display_unit {
+            Some(unit) => unit.symbol.clone(),
+            None => self.physical_quantity.to_string(),
+        }
+    }
+
+    pub fn is_absolute_temperature(&self) -> bool {
+        self.absolute_temperature
+    }
+
+    /// Shows this number in `system`'s unit for its physical quantity, unless
+    /// it has already been converted to a particular unit.
+    pub fn in_unit_system(self, system: units::UnitSystem) -> ConcreteNumber {
+        match (
+            &self.display_unit,
+            system.display_unit(self.physical_quantity),
+        ) {
+            (None, Some(unit)) => ConcreteNumberBuilder::new()
+                .magnitude(self.magnitude)
+                .physical_quantity(self.physical_quantity)
+                .display_unit(Some(unit))
+                .build(),
+            _ => self,
+        }
+    }
+
+    /// Writes this number with its magnitude in `notation`.
+    pub fn in_notation(&self, notation: Notation) -> NotationDisplay<'_> {
+        NotationDisplay {
+            number: self,
+            notation,
+            upper: false,
+        }
+    }
+
+    /// Writes the unit after a magnitude, with superscript exponents in the
+    /// alternate form.
+    pub(crate) fn write_unit(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.display_unit {
+            Some(unit) => write!(f, " {}", unit.symbol),
+            None if f.alternate() => write!(f, " {:#}", self.physical_quantity),
+            None => write!(f, " {}", self.physical_quantity),
+        }
+    }
+
+    /// Shows this number with the engineering SI prefix that suits its size,
+    /// so `0.000003 A` is shown as `3 µA` and `1500000000 W` as `1.5 GW`. This
+    /// only applies to quantities with a named unit that takes prefixes, and
+    /// not to numbers that already have a display unit.
+    pub fn with_best_unit(self) -> ConcreteNumber {
+        let Some(unit) = units::prefixable_unit(self.physical_quantity) else {
+            return self;
+        };
+        if self.display_unit.is_some() {
+            return self;
+        }
+        let power_of_ten = |factor: f64| factor.log10().round() as i32;
+        let (symbol, scale) = match prefix::engineering(self.magnitude / unit.factor) {
+            // Long times read better in seconds than in kiloseconds, and large
+            // masses in kilograms than in megagrams.
+            Some(prefix) if unit.symbol == "s" && prefix.factor > 1.0 => ("s".to_owned(), 1.0),
+            Some(prefix) if unit.symbol == "g" && prefix.factor > 1e3 => ("kg".to_owned(), 1.0),
+            // One power of ten, as the product of the two factors can be off
+            // in the last bit.
+            Some(prefix) => (
+                format!("{}{}", prefix.symbol, unit.symbol),
+                10f64.powi(power_of_ten(prefix.factor) + power_of_ten(unit.factor)),
+            ),
+            None => (unit.symbol.to_owned(), unit.factor),
+        };
+        let display_unit = DisplayUnit::new(&symbol, scale, 0.0, self.physical_quantity);
+        ConcreteNumber {
+            display_unit: Some(display_unit),
+            ..self
+        }
+    }
+
+    /// Converts to `unit` for display, which must measure the same physical
+    /// quantity. Converting to an affine temperature scale reads the value as
+    /// an absolute temperature, and converting away from one to kelvin or
+    /// rankine leaves a thermodynamic temperature that can be scaled freely.
+    pub fn convert_to(self, unit: DisplayUnit) -> Result<ConcreteNumber, CustomError> {
+        if self.physical_quantity != unit.physical_quantity {
+            Err(CustomError::ConvertingBetweenDifferentUnits {
+                from: self.physical_quantity,
+                to: unit.physical_quantity,
+            })
+        } else {
+            Ok(ConcreteNumberBuilder::new()
+                .magnitude(self.magnitude)
+                .physical_quantity(self.physical_quantity)
+                .absolute_temperature(unit.is_affine())
+                .display_unit(Some(unit))
+                .build())
+        }
+    }
+
+    /// The same number with angles counted as dimensionless, so `2 rad` is
+    /// just `2`.
+    pub fn without_angle(self) -> ConcreteNumber {
+        ConcreteNumber {
+            physical_quantity: self.physical_quantity.without_angle(),
+            display_unit: self.display_unit.map(DisplayUnit::without_angle),
+            ..self
+        }
+    }
+
+    /// Raises this number to the power `exponent`. A fractional power takes
+    /// a root of every dimension, so `(9 m^2)^(1/2)` is `3 m` and `(4 Hz)^(1/2)`
+    /// is `2 Hz^(1/2)`.
+    pub fn pow(self, exponent: Rational) -> Result<ConcreteNumber, CustomError> {
+        if self.absolute_temperature {
+            return Err(CustomError::RaisingAbsoluteTemperature {
+                temperature: Box::new(self),
+            });
+        }
+        if self.magnitude < 0.0 && exponent.denominator() % 2 == 0 {
+            return Err(CustomError::EvenRootOfNegative {
+                number: Box::new(self),
+                exponent,
+            });
+        }
+        let root = match exponent.denominator() {
+            1 => self.magnitude,
+            2 => self.magnitude.sqrt(),
+            3 => self.magnitude.cbrt(),
+            // Odd roots of negative numbers are real, but powf doesn't know.
+            degree if self.magnitude < 0.0 && degree % 2 != 0 => {
+                -(-self.magnitude).powf(1.0 / degree as f64)
+            }
+            degree => self.magnitude.powf(1.0 / degree as f64),
+        };
+        let physical_quantity = self
+            .physical_quantity
+            .checked_pow(exponent)
+            .ok_or(CustomError::ExponentOutOfRange)?;
+        Ok(ConcreteNumber::new(
+            root.powi(exponent.numerator().into()),
+            physical_quantity,
+        ))
+    }
+}
+
+/// Writes the magnitude and then the unit. Precision, width and sign flags
+/// apply to the magnitude, so `{:.2}` writes `0.30 m` for `0.1 m + 0.2 m`.
+impl Display for ConcreteNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.in_notation(Notation::Plain), f)
+    }
+}
+
+/// Writes the magnitude in scientific notation, e.g. `1.5e3 W`.
+impl LowerExp for ConcreteNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.in_notation(Notation::Scientific), f)
+    }
+}
+
+/// Writes the magnitude in scientific notation, e.g. `1.5E3 W`.
+impl UpperExp for ConcreteNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.in_notation(Notation::Scientific).uppercase(), f)
+    }
+}
+
+pub struct ConcreteNumberBuilder {
+    magnitude: f64,
+    physical_quantity: PhysicalQuantity,
+    display_unit: Option<DisplayUnit>,
+    absolute_temperature: bool,
+}
+
+impl ConcreteNumberBuilder {
+    pub fn new() -> Self {
+        Self {
+            magnitude: 0f64,
+            physical_quantity: PhysicalQuantityBuilder::new().build(),
+            display_unit: None,
+            absolute_temperature: false,
+        }
+    }
+
+    pub fn magnitude(mut self, magnitude: f64) -> Self {
+        self.magnitude = magnitude;
+        self
+    }
+
+    pub fn physical_quantity(mut self, physical_quantity: PhysicalQuantity) -> Self {
+        self.physical_quantity = physical_quantity;
+        self
+    }
+
+    pub fn display_unit(mut self, display_unit: Option<DisplayUnit>) -> Self {
+        self.display_unit = display_unit;
+        self
+    }
+
+    pub fn absolute_temperature(mut self, absolute_temperature: bool) -> Self {
+        self.absolute_temperature = absolute_temperature;
+        self
+    }
+
+    pub fn build(self) -> ConcreteNumber {
+        ConcreteNumber {
+            magnitude: self.magnitude,
+            physical_quantity: self.physical_quantity,
+            display_unit: self.display_unit,
+            absolute_temperature: self.absolute_temperature,
+        }
+    }
+}
+
+impl Default for ConcreteNumberBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The variables expressions are evaluated with. `ans` is the previous result.
+///
+/// Angles are dimensionless by default, as in the SI. With angle tracking on,
+/// angle is a dimension of its own, so `rad/s` and `Hz` are different
+/// quantities and a frequency can't be converted to an angular velocity.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    variables: HashMap<String, ConcreteNumber>,
+    track_angles: bool,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConcreteNumber> {
+        self.variables.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, value: ConcreteNumber) {
+        self.variables.insert(name.to_owned(), value);
+    }
+
+    pub fn tracks_angles(&self) -> bool {
+        self.track_angles
+    }
+
+    pub fn set_track_angles(&mut self, track_angles: bool) {
+        self.track_angles = track_angles;
+    }
+
+    /// `value` as this environment sees it: with its angles, or with them
+    /// counted as dimensionless.
+    fn normalize_angles(&self, value: ConcreteNumber) -> ConcreteNumber {
+        match self.track_angles {
+            true => value,
+            false => value.without_angle(),
+        }
+    }
+}
+
+/// A byte range `start..end` of the input, used to point diagnostics at the
+/// text they are about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceSpan {
+    start: usize,
+    end: usize,
+}
+
+impl SourceSpan {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(self) -> usize {
+        self.start
+    }
+
+    pub fn end(self) -> usize {
+        self.end
+    }
+
+    /// Renders a line of carets under this span of `source`, padded so it
+    /// lines up when printed directly beneath it.
+    pub fn underline(self, source: &str) -> String {
+        let before = source[..self.start].chars().count();
+        let width = source[self.start..self.end].chars().count().max(1);
+        format!("{}{}", " ".repeat(before), "^".repeat(width))
+    }
+}
+
+/// Everything that can go wrong parsing or evaluating an expression. Errors
+/// from evaluation are wrapped in `InSubExpression` once for each operation
+/// they happened inside, and `source()` walks that trail inwards.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum CustomError {
+    AddingTwoDifferentUnits {
+        lhs: PhysicalQuantity,
+        rhs: PhysicalQuantity,
+    },
+    SubtractingTwoDifferentUnits {
+        lhs: PhysicalQuantity,
+        rhs: PhysicalQuantity,
+    },
+    /// `source` happened while evaluating `expression`. Its message already
+    /// includes the message of `source`. The variables are those (and the
+    /// constants) used in the innermost expression that failed, with their
+    /// physical quantities.
+    InSubExpression {
+        expression: String,
+        span: SourceSpan,
+        variables: Vec<(String, PhysicalQuantity)>,
+        source: Box<CustomError>,
+    },
+    AddingTwoAbsoluteTemperatures {
+        lhs: Box<ConcreteNumber>,
+        rhs: Box<ConcreteNumber>,
+    },
+    SubtractingAbsoluteTemperatureFromDifference {
+        lhs: Box<ConcreteNumber>,
+        rhs: Box<ConcreteNumber>,
+    },
+    MultiplyingAbsoluteTemperature {
+        temperature: Box<ConcreteNumber>,
+    },
+    DividingAbsoluteTemperature {
+        temperature: Box<ConcreteNumber>,
+    },
+    ConvertingBetweenDifferentUnits {
+        from: PhysicalQuantity,
+        to: PhysicalQuantity,
+    },
+    UnknownUnit {
+        symbol: String,
+        span: SourceSpan,
+    },
+    /// The input stopped making sense at `found`, which runs to the end of
+    /// the input and is empty if the input ended too early.
+    ParseError {
+        found: String,
+        span: SourceSpan,
+    },
+    /// `ans` was used with no previous result to refer to.
+    NoPreviousResult {
+        span: SourceSpan,
+    },
+    UnknownVariable {
+        name: String,
+        span: SourceSpan,
+    },
+    RaisingAbsoluteTemperature {
+        temperature: Box<ConcreteNumber>,
+    },
+    UnknownFunction {
+        name: String,
+        span: SourceSpan,
+    },
+    WrongNumberOfArguments {
+        function: Function,
+        expected: usize,
+        found: usize,
+    },
+    /// A function that only makes sense for pure numbers, such as `ln` or
+    /// `sin`, was given a quantity with dimensions.
+    DimensionedArgument {
+        function: Function,
+        physical_quantity: PhysicalQuantity,
+    },
+    /// A function of two quantities, such as `max` or `atan2`, was given
+    /// quantities of different kinds.
+    MismatchedArguments {
+        function: Function,
+        lhs: PhysicalQuantity,
+        rhs: PhysicalQuantity,
+    },
+    FunctionOfAbsoluteTemperature {
+        function: Function,
+        temperature: Box<ConcreteNumber>,
+    },
+    /// A power was written after a number with a unit, as in `2 m ^ 2`, so
+    /// it could apply to the whole quantity or only to its unit.
+    AmbiguousPower {
+        quantity: String,
+        exponent: Rational,
+        span: SourceSpan,
+    },
+    /// A dimension's exponent grew beyond what a [`Rational`] can hold, as in
+    /// `(1 m^2)^20000`.
+    ExponentOutOfRange,
+    /// An even root, such as a square root, of a negative quantity.
+    EvenRootOfNegative {
+        number: Box<ConcreteNumber>,
+        exponent: Rational,
+    },
+}
+
+/// The broad kinds of error, for callers that react to them differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// The input isn't a well-formed expression, or names an unknown unit or
+    /// function.
+    Parse,
+    /// Quantities of different kinds were combined, converted or passed to a
+    /// function that can't take them.
+    Dimension,
+    /// Anything else that went wrong evaluating a well-formed expression.
+    Evaluation,
+}
+
+impl CustomError {
+    /// What kind of error this is, going by the innermost error in the trail.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            CustomError::InSubExpression { source, .. } => source.category(),
+            CustomError::UnknownUnit { .. }
+            | CustomError::ParseError { .. }
+            | CustomError::UnknownFunction { .. }
+            | CustomError::AmbiguousPower { .. } => ErrorCategory::Parse,
+            CustomError::AddingTwoDifferentUnits { .. }
+            | CustomError::SubtractingTwoDifferentUnits { .. }
+            | CustomError::ConvertingBetweenDifferentUnits { .. }
+            | CustomError::DimensionedArgument { .. }
+            | CustomError::MismatchedArguments { .. } => ErrorCategory::Dimension,
+            _ => ErrorCategory::Evaluation,
+        }
+    }
+
+    /// The part of the input the error is about: the innermost expression
+    /// that failed, or the text that could not be parsed.
+    pub fn span(&self) -> Option<SourceSpan> {
+        match self {
+            CustomError::InSubExpression { span, source, .. } => source.span().or(Some(*span)),
+            CustomError::UnknownUnit { span, .. }
+            | CustomError::ParseError { span, .. }
+            | CustomError::NoPreviousResult { span }
+            | CustomError::UnknownVariable { span, .. }
+            | CustomError::UnknownFunction { span, .. }
+            | CustomError::AmbiguousPower { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+}
+
+/// Lists variables and their kinds of quantity, e.g. "`m` is Mass and `t` is
+/// Time".
+fn describe_variables(variables: &[(String, PhysicalQuantity)]) -> String {
+    let descriptions: Vec<String> = variables
+        .iter()
+        .map(|(name, physical_quantity)| format!("`{}` is {}", name, physical_quantity.kind()))
+        .collect();
+    match descriptions.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+impl Display for CustomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CustomError::AddingTwoDifferentUnits { lhs, rhs } => {
+                write!(f, "cannot add {} to {}", lhs.kind(), rhs.kind())
+            }
+            CustomError::SubtractingTwoDifferentUnits { lhs, rhs } => {
+                write!(f, "cannot subtract {} from {}", rhs.kind(), lhs.kind())
+            }
+            CustomError::InSubExpression {
+                expression,
+                variables,
+                source,
+                ..
+            } => match **source {
+                CustomError::InSubExpression { .. } => {
+                    write!(f, "{}, within `{}`", source, expression)
+                }
+                _ if variables.is_empty() => write!(f, "{} in `{}`", source, expression),
+                _ => write!(
+                    f,
+                    "{} in `{}`, where {}",
+                    source,
+                    expression,
+                    describe_variables(variables)
+                ),
+            },
+            CustomError::AddingTwoAbsoluteTemperatures { lhs, rhs } => write!(
+                f,
+                "cannot add two absolute temperatures, {} and {}",
+                lhs, rhs
+            ),
+            CustomError::SubtractingAbsoluteTemperatureFromDifference { lhs, rhs } => write!(
+                f,
+                "cannot subtract the absolute temperature {} from the temperature difference {}",
+                rhs, lhs
+            ),
+            CustomError::MultiplyingAbsoluteTemperature { temperature } => {
+                write!(
+                    f,
+                    "cannot multiply the absolute temperature {}",
+                    temperature
+                )
+            }
+            CustomError::DividingAbsoluteTemperature { temperature } => {
+                write!(f, "cannot divide the absolute temperature {}", temperature)
+            }
+            CustomError::ConvertingBetweenDifferentUnits { from, to } => {
+                write!(f, "cannot convert {} to {}", from.kind(), to.kind())
+            }
+            CustomError::UnknownUnit { symbol, .. } => write!(f, "unknown unit `{}`", symbol),
+            CustomError::ParseError { found, .. } if found.is_empty() => {
+                write!(f, "unexpected end of input")
+            }
+            CustomError::ParseError { found, .. } => write!(f, "could not parse `{}`", found),
+            CustomError::NoPreviousResult { .. } => write!(f, "there is no previous result yet"),
+            CustomError::UnknownVariable { name, .. } => {
+                write!(f, "unknown variable `{}`", name)
+            }
+            CustomError::RaisingAbsoluteTemperature { temperature } => write!(
+                f,
+                "cannot raise the absolute temperature {} to a power",
+                temperature
+            ),
+            CustomError::UnknownFunction { name, .. } => {
+                write!(f, "unknown function `{}`", name)
+            }
+            CustomError::WrongNumberOfArguments {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} argument{}, not {}",
+                function,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+            CustomError::DimensionedArgument {
+                function: function @ (Function::Sin | Function::Cos | Function::Tan),
+                physical_quantity,
+            } => write!(
+                f,
+                "`{}` needs an angle or a dimensionless argument, not {}",
+                function,
+                physical_quantity.kind()
+            ),
+            CustomError::DimensionedArgument {
+                function,
+                physical_quantity,
+            } => write!(
+                f,
+                "`{}` needs a dimensionless argument, not {}",
+                function,
+                physical_quantity.kind()
+            ),
+            CustomError::MismatchedArguments { function, lhs, rhs } => write!(
+                f,
+                "`{}` needs arguments of the same kind, not {} and {}",
+                function,
+                lhs.kind(),
+                rhs.kind()
+            ),
+            CustomError::FunctionOfAbsoluteTemperature {
+                function,
+                temperature,
+            } => write!(
+                f,
+                "cannot take `{}` of the absolute temperature {}",
+                function, temperature
+            ),
+            CustomError::AmbiguousPower {
+                quantity, exponent, ..
+            } => {
+                let power = match exponent.is_integer() {
+                    true => format!("^{}", exponent),
+                    false => format!("^({})", exponent),
+                };
+                write!(
+                    f,
+                    "a power after `{quantity}` is ambiguous; write `({quantity}){power}` to \
+                     raise the whole quantity, or put the power on the unit without spaces"
+                )
+            }
+            CustomError::ExponentOutOfRange => {
+                write!(f, "the exponent of a dimension is out of range")
+            }
+            CustomError::EvenRootOfNegative { number, exponent } => write!(
+                f,
+                "cannot raise the negative quantity {} to the power {}",
+                number, exponent
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CustomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CustomError::InSubExpression { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl Add for ConcreteNumber {
+    type Output = Result<ConcreteNumber, crate::CustomError>;
+
+    /// A difference can be added to an absolute temperature, which keeps its
+    /// scale, but two absolute temperatures can't be added together.
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.physical_quantity != rhs.physical_quantity {
+            Err(CustomError::AddingTwoDifferentUnits {
+                lhs: self.physical_quantity,
+                rhs: rhs.physical_quantity,
+            })
+        } else if self.absolute_temperature && rhs.absolute_temperature {
+            Err(CustomError::AddingTwoAbsoluteTemperatures {
+                lhs: Box::new(self),
+                rhs: Box::new(rhs),
+            })
+        } else {
+            let quantity: f64 = self.magnitude + rhs.magnitude;
+            let (display_unit, absolute_temperature) = if rhs.absolute_temperature {
+                (rhs.display_unit, true)
+            } else {
+                (self.display_unit, self.absolute_temperature)
+            };
+            let sum: ConcreteNumber = ConcreteNumberBuilder::new()
+                .magnitude(quantity)
+                .physical_quantity(self.physical_quantity)
+                .display_unit(display_unit)
+                .absolute_temperature(absolute_temperature)
+                .build();
+            Ok(sum)
+        }
+    }
+}
+
+impl Sub for ConcreteNumber {
+    type Output = Result<ConcreteNumber, crate::CustomError>;
+
+    /// Subtracting a difference from an absolute temperature gives another
+    /// absolute temperature, and subtracting two absolute temperatures gives
+    /// the difference between them in kelvin.
+    fn sub(self, rhs: Self) -> Self::Output {
+        if self.physical_quantity != rhs.physical_quantity {
+            Err(CustomError::SubtractingTwoDifferentUnits {
+                lhs: self.physical_quantity,
+                rhs: rhs.physical_quantity,
+            })
+        } else if rhs.absolute_temperature && !self.absolute_temperature {
+            Err(CustomError::SubtractingAbsoluteTemperatureFromDifference {
+                lhs: Box::new(self),
+                rhs: Box::new(rhs),
+            })
+        } else {
+            let quantity: f64 = self.magnitude - rhs.magnitude;
+            let (display_unit, absolute_temperature) = if rhs.absolute_temperature {
+                (None, false)
+            } else {
+                (self.display_unit, self.absolute_temperature)
+            };
+            let difference: ConcreteNumber = ConcreteNumberBuilder::new()
+                .magnitude(quantity)
+                .physical_quantity(self.physical_quantity)
+                .display_unit(display_unit)
+                .absolute_temperature(absolute_temperature)
+                .build();
+            Ok(difference)
+        }
+    }
+}
+
+impl Neg for ConcreteNumber {
+    type Output = ConcreteNumber;
+
+    /// Negating an absolute temperature negates its reading on its own scale,
+    /// so `-20 °C` is twenty degrees below freezing rather than below
+    /// absolute zero.
+    fn neg(self) -> Self::Output {
+        let magnitude = match (&self.display_unit, self.absolute_temperature) {
+            (Some(unit), true) => {
+                let reading = self.magnitude / unit.scale - unit.offset;
+                (unit.offset - reading) * unit.scale
+            }
+            _ => -self.magnitude,
+        };
+        ConcreteNumber { magnitude, ..self }
+    }
+}
+
+/// The first of two operands that is an absolute temperature, if either is.
+fn absolute_operand<'a>(
+    lhs: &'a ConcreteNumber,
+    rhs: &'a ConcreteNumber,
+) -> Option<&'a ConcreteNumber> {
+    [lhs, rhs]
+        .into_iter()
+        .find(|operand| operand.absolute_temperature)
+}
+
+impl Mul for ConcreteNumber {
+    type Output = Result<ConcreteNumber, crate::CustomError>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        if let Some(temperature) = absolute_operand(&self, &rhs) {
+            return Err(CustomError::MultiplyingAbsoluteTemperature {
+                temperature: Box::new(temperature.clone()),
+            });
+        }
+        let quantity: f64 = self.magnitude * rhs.magnitude;
+        let unit: PhysicalQuantity = self
+            .physical_quantity
+            .checked_mul(rhs.physical_quantity)
+            .ok_or(CustomError::ExponentOutOfRange)?;
+        Ok(ConcreteNumber::new(quantity, unit))
+    }
+}
+
+impl Div for ConcreteNumber {
+    type Output = Result<ConcreteNumber, crate::CustomError>;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        if let Some(temperature) = absolute_operand(&self, &rhs) {
+            return Err(CustomError::DividingAbsoluteTemperature {
+                temperature: Box::new(temperature.clone()),
+            });
+        }
+        let quantity: f64 = self.magnitude / rhs.magnitude;
+        let unit: PhysicalQuantity = self
+            .physical_quantity
+            .checked_div(rhs.physical_quantity)
+            .ok_or(CustomError::ExponentOutOfRange)?;
+        Ok(ConcreteNumber::new(quantity, unit))
+    }
+}
+
+impl From<f64> for ConcreteNumber {
+    fn from(value: f64) -> Self {
+        ConcreteNumberBuilder::new().magnitude(value).build()
+    }
+}
+
+/// Parses `input` into an expression tree without evaluating it.
+pub fn parse(input: &str) -> Result<Expr, CustomError> {
+    parser::parse(input)
+}
+
+/// Parses and evaluates `input`, e.g. `"3 km / 20 min to km/h"`.
+pub fn evaluate(input: &str) -> Result<ConcreteNumber, CustomError> {
+    parser::evaluate_physical_equation(input)
+}
+
+/// Like [`evaluate`], reading variables from `environment` and binding any
+/// `let`s in the input there.
+pub fn evaluate_in(
+    input: &str,
+    environment: &mut Environment,
+) -> Result<ConcreteNumber, CustomError> {
+    parser::evaluate_in(input, environment)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        notation::Notation, parser, rational::Rational, units::UnitSystem, ConcreteNumber,
+        CustomError, Environment, ErrorCategory, PhysicalQuantity, PhysicalQuantityBuilder,
+        SourceSpan,
+    };
+
+    /// Evaluates `input`, which must fail, and strips the sub-expression
+    /// trail off the error.
+    fn innermost_error(input: &str) -> CustomError {
+        let mut error = parser::evaluate_physical_equation(input).unwrap_err();
+        while let CustomError::InSubExpression { source, .. } = error {
+            error = *source;
+        }
+        error
+    }
+
+    #[test]
+    fn build_metre() {
+        let length_unit: PhysicalQuantity = PhysicalQuantityBuilder::new().length(1).build();
+        let length: ConcreteNumber = ConcreteNumber::new(13.0, length_unit);
+        assert_eq!(format!("{}", length), "13 m")
+    }
+
+    #[test]
+    fn build_time() {
+        let time_unit: PhysicalQuantity = PhysicalQuantityBuilder::new().time(1).build();
+        let time: ConcreteNumber = ConcreteNumber::new(2.0, time_unit);
+        assert_eq!(format!("{}", time), "2 s");
+    }
+
+    #[test]
+    fn build_acceleration() {
+        let length_unit: PhysicalQuantity = PhysicalQuantityBuilder::new().length(1).build();
+        let length: ConcreteNumber = ConcreteNumber::new(13.0, length_unit);
+        let time_unit: PhysicalQuantity = PhysicalQuantityBuilder::new().time(1).build();
+        let time: ConcreteNumber = ConcreteNumber::new(2.0, time_unit);
+        let acceleration = (length / (time.clone() * time).unwrap()).unwrap();
+        assert_eq!(format!("{}", acceleration), "3.25 m/s^2");
+    }
+
+    #[test]
+    fn parse_time_and_print() {
+        let time: &str = "3 s";
+        let concrete_time = parser::evaluate_physical_equation(time).unwrap();
+        assert_eq!(format!("{}", concrete_time), time);
+    }
+
+    #[test]
+    fn explicit_and_implicit_unit_multiplication_should_match() {
+        let cn_1 = "3 W m^3";
+        let cn_2 = "3 W * 1 m * 1 m^2";
+        assert_eq!(
+            parser::evaluate_physical_equation(cn_1),
+            parser::evaluate_physical_equation(cn_2)
+        );
+    }
+
+    #[test]
+    fn si_prefixes_scale_the_magnitude() {
+        assert_eq!(
+            parser::evaluate_physical_equation("5 km"),
+            parser::evaluate_physical_equation("5000 m")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("3 mA"),
+            parser::evaluate_physical_equation("0.003 A")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("2 km^2"),
+            parser::evaluate_physical_equation("2000000 m^2")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("4 µs"),
+            parser::evaluate_physical_equation("4 us")
+        );
+    }
+
+    #[test]
+    fn exact_symbols_win_over_prefixed_readings() {
+        let metre = parser::evaluate_physical_equation("1 m").unwrap();
+        assert_eq!(format!("{}", metre), "1 m");
+        let candela = parser::evaluate_physical_equation("1 cd").unwrap();
+        assert_eq!(format!("{}", candela), "1 cd");
+        let pascal = parser::evaluate_physical_equation("2 hPa").unwrap();
+        assert_eq!(format!("{}", pascal), "200 Pa");
+    }
+
+    #[test]
+    fn prefixes_attach_to_grams() {
+        let kilogram = parser::evaluate_physical_equation("1 kg").unwrap();
+        assert_eq!(format!("{}", kilogram), "1 kg");
+        let milligram = parser::evaluate_physical_equation("1000000 mg").unwrap();
+        assert_eq!(format!("{}", milligram), "1 kg");
+    }
+
+    #[test]
+    fn unknown_units_are_rejected() {
+        assert_eq!(
+            parser::evaluate_physical_equation("3 mtr"),
+            Err(CustomError::UnknownUnit {
+                symbol: "mtr".to_owned(),
+                span: SourceSpan::new(2, 5),
+            })
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("1 m + (2 m * 4 furlongs)"),
+            Err(CustomError::UnknownUnit {
+                symbol: "furlongs".to_owned(),
+                span: SourceSpan::new(15, 23),
+            })
+        );
+    }
+
+    #[test]
+    fn convert_to_requested_unit() {
+        let distance = parser::evaluate_physical_equation("3 km + 500 m to km").unwrap();
+        assert_eq!(format!("{}", distance), "3.5 km");
+        let energy = parser::evaluate_physical_equation("2 kJ in J").unwrap();
+        assert_eq!(format!("{}", energy), "2000 J");
+    }
+
+    #[test]
+    fn converting_between_different_units_fails() {
+        let length = PhysicalQuantityBuilder::new().length(1).build();
+        let time = PhysicalQuantityBuilder::new().time(1).build();
+        assert_eq!(
+            innermost_error("2 m to s"),
+            CustomError::ConvertingBetweenDifferentUnits {
+                from: length,
+                to: time,
+            }
+        );
+    }
+
+    #[test]
+    fn non_si_units_are_scaled_to_si() {
+        let distance = parser::evaluate_physical_equation("2 h * 60 km h^-1").unwrap();
+        assert_eq!(
+            distance.physical_quantity,
+            PhysicalQuantityBuilder::new().length(1).build()
+        );
+        assert!((distance.magnitude - 120000.0).abs() < 1e-9);
+        let energy = parser::evaluate_physical_equation("1 kWh in J").unwrap();
+        assert_eq!(format!("{}", energy), "3600000 J");
+        let pressure = parser::evaluate_physical_equation("1 atm to kPa").unwrap();
+        assert_eq!(format!("{}", pressure), "101.325 kPa");
+    }
+
+    #[test]
+    fn unit_aliases_match_their_symbol() {
+        assert_eq!(
+            parser::evaluate_physical_equation("90 minutes to h"),
+            parser::evaluate_physical_equation("1.5 h to h")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("250 ml"),
+            parser::evaluate_physical_equation("250 mL")
+        );
+    }
+
+    #[test]
+    fn imperial_and_us_customary_units() {
+        let length = parser::evaluate_physical_equation("1 mi to yd").unwrap();
+        assert_eq!(format!("{}", length), "1760 yd");
+        let mass = parser::evaluate_physical_equation("1 lb to g").unwrap();
+        assert_eq!(format!("{}", mass), "453.59237 g");
+        let us = parser::evaluate_physical_equation("1 gal to L").unwrap();
+        let imperial = parser::evaluate_physical_equation("1 impgal to L").unwrap();
+        assert!(imperial.magnitude > us.magnitude);
+    }
+
+    #[test]
+    fn display_in_unit_system() {
+        let force = parser::evaluate_physical_equation("2 lbf").unwrap();
+        assert_eq!(
+            format!("{}", force.in_unit_system(UnitSystem::UsCustomary)),
+            "2 lbf"
+        );
+        let explicit = parser::evaluate_physical_equation("1 ft to m").unwrap();
+        assert_eq!(
+            format!("{}", explicit.in_unit_system(UnitSystem::Imperial)),
+            "0.3048 m"
+        );
+        let time = parser::evaluate_physical_equation("2 s").unwrap();
+        assert_eq!(
+            format!("{}", time.in_unit_system(UnitSystem::Imperial)),
+            "2 s"
+        );
+    }
+
+    #[test]
+    fn absolute_temperatures_take_differences() {
+        let warmer = parser::evaluate_physical_equation("20 °C + 5 K").unwrap();
+        assert_eq!(format!("{}", warmer), "25 °C");
+        let interval = parser::evaluate_physical_equation("30 °C - 20 °C").unwrap();
+        assert_eq!(format!("{}", interval), "10 K");
+        let boiling = parser::evaluate_physical_equation("100 °C to K").unwrap();
+        assert_eq!(format!("{}", boiling), "373.15 K");
+        let absolute_zero = parser::evaluate_physical_equation("0 K to degF").unwrap();
+        assert_eq!(format!("{}", absolute_zero), "-459.67 degF");
+    }
+
+    #[test]
+    fn absolute_temperatures_cannot_be_scaled_or_summed() {
+        assert!(matches!(
+            innermost_error("20 °C * 2"),
+            CustomError::MultiplyingAbsoluteTemperature { .. }
+        ));
+        assert!(matches!(
+            innermost_error("20 °C + 20 °C"),
+            CustomError::AddingTwoAbsoluteTemperatures { .. }
+        ));
+        assert!(matches!(
+            innermost_error("5 K - 20 °C"),
+            CustomError::SubtractingAbsoluteTemperatureFromDifference { .. }
+        ));
+    }
+
+    #[test]
+    fn unit_expressions_with_division_and_groups() {
+        let acceleration = PhysicalQuantityBuilder::new().time(-2).length(1).build();
+        let gravity = parser::evaluate_physical_equation("9.81 m/s^2").unwrap();
+        assert_eq!(gravity.physical_quantity, acceleration);
+        assert_eq!(gravity.magnitude, 9.81);
+        assert_eq!(
+            parser::evaluate_physical_equation("3 kg/(m s^2)"),
+            parser::evaluate_physical_equation("3 Pa")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("2 (m/s)^2"),
+            parser::evaluate_physical_equation("2 m^2 s^-2")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("8.314 J/mol K"),
+            parser::evaluate_physical_equation("8.314 J mol^-1 K^-1")
+        );
+    }
+
+    #[test]
+    fn unit_product_separators() {
+        let joule = parser::evaluate_physical_equation("5 J");
+        assert_eq!(parser::evaluate_physical_equation("5 N·m"), joule);
+        assert_eq!(parser::evaluate_physical_equation("5 N.m"), joule);
+        assert_eq!(parser::evaluate_physical_equation("5 N*m"), joule);
+    }
+
+    #[test]
+    fn spaced_operators_still_divide_numbers() {
+        let speed = parser::evaluate_physical_equation("10 m / 2 s").unwrap();
+        assert_eq!(format!("{}", speed), "5 m/s");
+    }
+
+    #[test]
+    fn superscript_and_alternative_exponents() {
+        let per_square_metre = parser::evaluate_physical_equation("3 m^-2");
+        assert_eq!(
+            parser::evaluate_physical_equation("3 m⁻²"),
+            per_square_metre
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("3 m^−2"),
+            per_square_metre
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("3 m**-2"),
+            per_square_metre
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("1 kg·m²·s⁻³"),
+            parser::evaluate_physical_equation("1 W")
+        );
+    }
+
+    #[test]
+    fn alternate_display_uses_superscripts() {
+        let acceleration = parser::evaluate_physical_equation("9.81 m/s^2").unwrap();
+        assert_eq!(format!("{:#}", acceleration), "9.81 m/s²");
+        assert_eq!(format!("{}", acceleration), "9.81 m/s^2");
+    }
+
+    #[test]
+    fn inner_errors_keep_their_dimensions_and_trail() {
+        let length = PhysicalQuantityBuilder::new().length(1).build();
+        let time = PhysicalQuantityBuilder::new().time(1).build();
+        let error = parser::evaluate_physical_equation("1 m * (3 m + 2 s)").unwrap_err();
+        assert_eq!(
+            error,
+            CustomError::InSubExpression {
+                expression: "1 m * (3 m + 2 s)".to_owned(),
+                span: SourceSpan::new(0, 17),
+                variables: vec![],
+                source: Box::new(CustomError::InSubExpression {
+                    expression: "3 m + 2 s".to_owned(),
+                    span: SourceSpan::new(7, 16),
+                    variables: vec![],
+                    source: Box::new(CustomError::AddingTwoDifferentUnits {
+                        lhs: length,
+                        rhs: time,
+                    }),
+                }),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "cannot add Length to Time in `3 m + 2 s`, within `1 m * (3 m + 2 s)`"
+        );
+    }
+
+    #[test]
+    fn errors_point_at_their_source() {
+        let input = "2 s + (1 km + 3 kg)";
+        let error = parser::evaluate_physical_equation(input).unwrap_err();
+        let span = error.span().unwrap();
+        assert_eq!(span, SourceSpan::new(7, 18));
+        assert_eq!(span.underline(input), "       ^^^^^^^^^^^");
+
+        let input = "20 °C + 3 mtr";
+        let span = parser::evaluate_physical_equation(input)
+            .unwrap_err()
+            .span()
+            .unwrap();
+        assert_eq!(span.underline(input), "          ^^^");
+
+        let error = parser::evaluate_physical_equation("1 m + 2 m )").unwrap_err();
+        assert_eq!(error.span(), Some(SourceSpan::new(10, 11)));
+    }
+
+    #[test]
+    fn library_entry_points_and_accessors() {
+        let speed = crate::evaluate("3 km / 20 min to km/h").unwrap();
+        assert_eq!(speed.magnitude(), 2.5);
+        assert_eq!(speed.physical_quantity().length(), 1);
+        assert_eq!(speed.physical_quantity().time(), -1);
+        assert_eq!(speed.display_unit().unwrap().symbol(), "km/h");
+
+        let expression = crate::parse("1 m + (2 m)").unwrap();
+        assert!(matches!(expression.kind, crate::ExprKind::Add(..)));
+        assert_eq!(expression.span, SourceSpan::new(0, 11));
+    }
+
+    #[test]
+    fn errors_chain_their_sources() {
+        use std::error::Error;
+
+        fn evaluate(input: &str) -> Result<ConcreteNumber, Box<dyn Error>> {
+            Ok(crate::evaluate(input)?)
+        }
+
+        let error = evaluate("2 * (20 °C + 30 °C)").unwrap_err();
+        let inner = error.source().unwrap();
+        assert_eq!(
+            inner.to_string(),
+            "cannot add two absolute temperatures, 20 °C and 30 °C in `20 °C + 30 °C`"
+        );
+        assert!(inner.source().unwrap().source().is_none());
+
+        assert_eq!(
+            crate::evaluate("").unwrap_err().to_string(),
+            "unexpected end of input"
+        );
+        assert_eq!(
+            crate::evaluate("3 m +").unwrap_err().to_string(),
+            "could not parse `+`"
+        );
+        assert_eq!(
+            crate::evaluate("3 m $ 2").unwrap_err().to_string(),
+            "could not parse `$ 2`"
+        );
+    }
+
+    #[test]
+    fn ans_refers_to_the_previous_result() {
+        let mut environment = Environment::new();
+        assert_eq!(
+            crate::evaluate_in("1 m + ans", &mut environment)
+                .unwrap_err()
+                .span(),
+            Some(SourceSpan::new(6, 9))
+        );
+        environment.set("ans", crate::evaluate("3 km").unwrap());
+        assert_eq!(
+            crate::evaluate_in("ans / 20 min to km/h", &mut environment)
+                .unwrap()
+                .to_string(),
+            "9 km/h"
+        );
+        assert_eq!(
+            crate::evaluate_in("2 * _", &mut environment).unwrap(),
+            crate::evaluate("6 km").unwrap()
+        );
+    }
+
+    #[test]
+    fn let_bindings_define_variables() {
+        let weight = crate::evaluate("let m = 70 kg; let g = 9.81 m/s^2; m * g").unwrap();
+        assert_eq!(weight, crate::evaluate("686.7 N").unwrap());
+
+        let mut environment = Environment::new();
+        crate::evaluate_in("let side = 3 m;", &mut environment).unwrap();
+        assert_eq!(
+            crate::evaluate_in("side * side to cm^2", &mut environment)
+                .unwrap()
+                .to_string(),
+            "90000 cm^2"
+        );
+        assert_eq!(
+            innermost_error("side * 2").to_string(),
+            "unknown variable `side`"
+        );
+    }
+
+    #[test]
+    fn variables_may_start_like_special_numbers() {
+        let inflow = crate::evaluate("let inflow = 3 m; inflow").unwrap();
+        assert_eq!(inflow, crate::evaluate("3 m").unwrap());
+        let nano = crate::evaluate("let nano = 2; let Info = 3; nano * Info").unwrap();
+        assert_eq!(nano, crate::evaluate("6").unwrap());
+        assert_eq!(
+            crate::evaluate(".5 m").unwrap(),
+            crate::evaluate("0.5 m").unwrap()
+        );
+    }
+
+    #[test]
+    fn dimension_errors_name_variables() {
+        assert_eq!(
+            crate::evaluate("let m = 70 kg; let t = 3 s; 2 * (m + t)")
+                .unwrap_err()
+                .to_string(),
+            "cannot add Mass to Time in `m + t`, where `m` is Mass and `t` is Time, \
+             within `2 * (m + t)`"
+        );
+        assert_eq!(
+            crate::evaluate("let t = 3 s; 2 m - t")
+                .unwrap_err()
+                .to_string(),
+            "cannot subtract Time from Length in `2 m - t`, where `t` is Time"
+        );
+    }
+
+    #[test]
+    fn constants_are_referenced_by_name() {
+        let photon = parser::evaluate_physical_equation("h * 500 THz").unwrap();
+        assert_eq!(
+            photon.physical_quantity(),
+            parser::evaluate_physical_equation("1 J")
+                .unwrap()
+                .physical_quantity()
+        );
+        assert!((photon.magnitude() - 3.313_035_075e-19).abs() < 1e-30);
+        assert_eq!(
+            parser::evaluate_physical_equation("c * 1 s to km")
+                .unwrap()
+                .to_string(),
+            "299792.458 km"
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("2 C + e")
+                .unwrap()
+                .physical_quantity(),
+            parser::evaluate_physical_equation("1 A s")
+                .unwrap()
+                .physical_quantity()
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("let c = 3 m; c").unwrap(),
+            parser::evaluate_physical_equation("3 m").unwrap()
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("g0 - 1 m")
+                .unwrap_err()
+                .to_string(),
+            "cannot subtract Length from Acceleration in `g0 - 1 m`, where `g0` is Acceleration"
+        );
+    }
+
+    #[test]
+    fn powers_and_roots_of_quantities() {
+        assert_eq!(
+            parser::evaluate_physical_equation("(3 m)^2"),
+            parser::evaluate_physical_equation("9 m^2")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("(2 m) ** 3"),
+            parser::evaluate_physical_equation("8 m^3")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("2 m**3"),
+            parser::evaluate_physical_equation("2 m^3")
+        );
+        assert_eq!(
+            innermost_error("2 m ^ 2").to_string(),
+            "a power after `2 m` is ambiguous; write `(2 m)^2` to raise the whole \
+             quantity, or put the power on the unit without spaces"
+        );
+        assert!(matches!(
+            innermost_error("2 m ** 3"),
+            CustomError::AmbiguousPower { .. }
+        ));
+        assert!(matches!(
+            innermost_error("sqrt(-4 m^2)"),
+            CustomError::EvenRootOfNegative { .. }
+        ));
+        assert_eq!(
+            parser::evaluate_physical_equation("2^-1"),
+            parser::evaluate_physical_equation("0.5")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("sqrt(2 * 8 m/s^2 * 4 m)"),
+            parser::evaluate_physical_equation("8 m/s")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("(8 m^3)^(2/3)"),
+            parser::evaluate_physical_equation("4 m^2")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("cbrt(-27 m^3)"),
+            parser::evaluate_physical_equation("-3 m")
+        );
+        assert!(matches!(
+            innermost_error("(20 °C)^2"),
+            CustomError::RaisingAbsoluteTemperature { .. }
+        ));
+    }
+
+    #[test]
+    fn fractional_dimension_exponents() {
+        let noise = parser::evaluate_physical_equation("3 V/Hz^(1/2)").unwrap();
+        assert_eq!(noise.physical_quantity().time(), Rational::new(-5, 2));
+        assert_eq!(format!("{}", noise), "3 V s^(1/2)");
+        assert_eq!(format!("{:#}", noise), "3 V s¹ᐟ²");
+        assert_eq!(
+            parser::evaluate_physical_equation("3 V Hz^-1/2"),
+            Ok(noise.clone())
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("3 m^2/2"),
+            parser::evaluate_physical_equation("1.5 m^2")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("6 m^3/3"),
+            parser::evaluate_physical_equation("2 m^3")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("3 A⁻¹ kg m² s⁻⁵ᐟ²"),
+            Ok(noise)
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("(4 V/Hz^(1/2))^2"),
+            parser::evaluate_physical_equation("16 V^2/Hz")
+        );
+
+        let root = parser::evaluate_physical_equation("sqrt(4 m)").unwrap();
+        assert_eq!(format!("{}", root), "2 m^(1/2)");
+        assert_eq!(root.physical_quantity().kind(), "Length^(1/2)");
+        assert_eq!(
+            parser::evaluate_physical_equation("2 MPa m^(1/2)")
+                .unwrap()
+                .physical_quantity()
+                .length(),
+            Rational::new(-1, 2)
+        );
+    }
+
+    #[test]
+    fn functions_follow_dimensional_rules() {
+        assert_eq!(
+            parser::evaluate_physical_equation("hypot(3 m, 400 cm)"),
+            parser::evaluate_physical_equation("5 m")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("max(2 km, 300 m) + abs(-1 m)"),
+            parser::evaluate_physical_equation("2001 m")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("floor(2.7 s) * ceil(0.2)"),
+            parser::evaluate_physical_equation("2 s")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("ln(exp(2)) + log10(1000)"),
+            parser::evaluate_physical_equation("5")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("atan2(1 m, 1 m) * 4"),
+            Ok(ConcreteNumber::from(std::f64::consts::PI))
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("sin(2 m)")
+                .unwrap_err()
+                .to_string(),
+            "`sin` needs an angle or a dimensionless argument, not Length in `sin(2 m)`"
+        );
+        assert!(matches!(
+            innermost_error("min(1 m, 1 s)"),
+            CustomError::MismatchedArguments { .. }
+        ));
+        assert!(matches!(
+            innermost_error("exp(1, 2)"),
+            CustomError::WrongNumberOfArguments {
+                expected: 1,
+                found: 2,
+                ..
+            }
+        ));
+        assert_eq!(
+            parser::evaluate_physical_equation("foo(3)"),
+            Err(CustomError::UnknownFunction {
+                name: "foo".to_owned(),
+                span: SourceSpan::new(0, 3)
+            })
+        );
+    }
+
+    #[test]
+    fn prefix_signs() {
+        assert_eq!(
+            parser::evaluate_physical_equation("-(3 m + 2 m)"),
+            parser::evaluate_physical_equation("0 m - 5 m")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("2 m * -1 - +1 m"),
+            parser::evaluate_physical_equation("0 m - 3 m")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("-2^2"),
+            parser::evaluate_physical_equation("0 - 4")
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("- -20 °C"),
+            parser::evaluate_physical_equation("20 °C")
+        );
+        assert_eq!(
+            format!("{:?}", parser::parse("-3 m * 2").unwrap()),
+            "((-3 m) * 2 dimensionless)"
+        );
+    }
+
+    #[test]
+    fn angles_are_dimensionless_unless_tracked() {
+        assert_eq!(
+            parser::evaluate_physical_equation("180° to rad")
+                .unwrap()
+                .magnitude(),
+            std::f64::consts::PI
+        );
+        assert_eq!(
+            parser::evaluate_physical_equation("3 rad/s"),
+            parser::evaluate_physical_equation("3 Hz")
+        );
+
+        let mut environment = Environment::new();
+        environment.set_track_angles(true);
+        let spin = crate::evaluate_in("60 rpm to rad/s", &mut environment).unwrap();
+        assert_eq!(spin.physical_quantity().kind(), "AngularVelocity");
+        assert_eq!(spin.magnitude(), 2.0 * std::f64::consts::PI);
+        assert_eq!(
+            crate::evaluate_in("1 rpm to Hz", &mut environment)
+                .unwrap_err()
+                .to_string(),
+            "cannot convert AngularVelocity to Frequency in `1 rpm to Hz`"
+        );
+        assert_eq!(
+            crate::evaluate_in("4 sr", &mut environment)
+                .unwrap()
+                .physical_quantity()
+                .kind(),
+            "SolidAngle"
+        );
+        assert_eq!(
+            crate::evaluate_in("cos(0.5 turn)", &mut environment),
+            Ok(ConcreteNumber::from(-1.0))
+        );
+        assert_eq!(
+            crate::evaluate_in("ln(2 rad)", &mut environment)
+                .unwrap_err()
+                .to_string(),
+            "`ln` needs a dimensionless argument, not Angle in `ln(2 rad)`"
+        );
+    }
+
+    #[test]
+    fn errors_have_categories() {
+        let category = |input| {
+            parser::evaluate_physical_equation(input)
+                .unwrap_err()
+                .category()
+        };
+        assert_eq!(category("3 m +"), ErrorCategory::Parse);
+        assert_eq!(category("3 furlongs"), ErrorCategory::Parse);
+        assert_eq!(category("(3 m + 2 s) * 2"), ErrorCategory::Dimension);
+        assert_eq!(category("1 m to s"), ErrorCategory::Dimension);
+        assert_eq!(category("20 °C * 2"), ErrorCategory::Evaluation);
+
+        let speed = parser::evaluate_physical_equation("36 km/h to m/s").unwrap();
+        assert_eq!(speed.displayed_magnitude(), 10.0);
+        assert_eq!(speed.unit_symbol(), "m/s");
+        let force = parser::evaluate_physical_equation("2 kg m/s^2").unwrap();
+        assert_eq!(force.unit_symbol(), "N");
+    }
+
+    #[test]
+    fn best_unit_picks_an_engineering_prefix() {
+        let best = |input| {
+            parser::evaluate_physical_equation(input)
+                .unwrap()
+                .with_best_unit()
+                .to_string()
+        };
+        assert_eq!(best("0.000003 A"), "3 µA");
+        assert_eq!(best("1500000000 W"), "1.5 GW");
+        assert_eq!(best("2 kg m/s^2"), "2 N");
+        assert_eq!(best("250 g"), "250 g");
+        assert_eq!(best("0.5 g"), "500 mg");
+        assert_eq!(best("1 t"), "1000 kg");
+        assert_eq!(best("1000 kg"), "1000 kg");
+        assert_eq!(best("2 kg"), "2 kg");
+        assert_eq!(best("7200 s"), "7200 s");
+        assert_eq!(best("2 m/s"), "2 m/s");
+        assert!(best("3 km to mi").ends_with(" mi"));
+    }
+
+    #[test]
+    fn derived_units_simplify_compound_quantities() {
+        let unit = |input| {
+            parser::evaluate_physical_equation(input)
+                .unwrap()
+                .physical_quantity()
+        };
+        assert_eq!(unit("3 kg m/s").to_string(), "N s");
+        assert_eq!(unit("4 W/m/K").to_string(), "W/(m K)");
+        assert_eq!(unit("5 N/m").to_string(), "N/m");
+        assert_eq!(unit("1 A/m^2").to_string(), "A/m^2");
+        assert_eq!(unit("1 C/m^3").to_string(), "C/m^3");
+        assert_eq!(unit("1 S/m").to_string(), "S/m");
+        assert_eq!(unit("9.81 m/s^2").to_string(), "m/s^2");
+        assert_eq!(unit("1 s^-2").to_string(), "s^-2");
+        assert_eq!(unit("2 W").with_units(&["J"]).to_string(), "J/s");
+        assert_eq!(unit("2 W").with_units(&[]).to_string(), "kg m^2/s^3");
+    }
+
+    #[test]
+    fn concrete_numbers_honour_format_specifiers() {
+        let number = |input| parser::evaluate_physical_equation(input).unwrap();
+        let sum = number("0.1 m + 0.2 m");
+        assert_eq!(format!("{:.2}", sum), "0.30 m");
+        assert_eq!(format!("{:>6.1}|", sum), "   0.3 m|");
+        assert_eq!(format!("{:+}", number("3 m")), "+3 m");
+        assert_eq!(format!("{:e}", number("1500 W")), "1.5e3 W");
+        assert_eq!(format!("{:.2E}", number("1500 W")), "1.50E3 W");
+
+        let engineering = |input, precision| {
+            let number = number(input);
+            let number = number.in_notation(Notation::Engineering);
+            match precision {
+                Some(precision) => format!("{:.*}", precision, number),
+                None => number.to_string(),
+            }
+        };
+        assert_eq!(engineering("0.000015 A", None), "15e-6 A");
+        assert_eq!(engineering("1500 W", None), "1.5e3 W");
+        assert_eq!(engineering("12 m", None), "12e0 m");
+        assert_eq!(engineering("-999.96 m", Some(1)), "-1.0e3 m");
+        assert_eq!(engineering("0 m", None), "0 m");
+        let current = number("0.000015 A");
+        let uppercase = current.in_notation(Notation::Engineering).uppercase();
+        assert_eq!(format!("{:.1}", uppercase), "15.0E-6 A");
+    }
+
+    #[test]
+    fn builders_divide_by_subtracting_exponents() {
+        let speed =
+            PhysicalQuantityBuilder::new().length(1) / PhysicalQuantityBuilder::new().time(1);
+        assert_eq!(
+            speed.build(),
+            PhysicalQuantityBuilder::new().length(1).time(-1).build()
+        );
+    }
+
+    #[test]
+    fn exponents_out_of_range_are_errors() {
+        for input in [
+            "(1 m^2)^20000",
+            "1 m^20000 * 1 m^20000",
+            "1 m^20000 / 1 m^-20000",
+            "1 m^20000 m^20000",
+        ] {
+            assert!(
+                matches!(innermost_error(input), CustomError::ExponentOutOfRange),
+                "{input}"
+            );
+        }
+        assert_eq!(Rational::integer(i16::MAX).checked_add(Rational::ONE), None);
+
+        // Results whose exponents are in range but too large to simplify are
+        // still shown.
+        let shown = |input| crate::evaluate(input).unwrap().to_string();
+        assert_eq!(shown("1 m^20000 kg^20000"), "1 kg^20000 m^20000");
+        assert_eq!(shown("1 m^-16384 * 1 m^-16384"), "1 m^-32768");
+    }
+}
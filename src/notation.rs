@@ -0,0 +1,141 @@
+use std::{
+    fmt::{self, Alignment, Display, Formatter, LowerExp, UpperExp},
+    str::FromStr,
+};
+
+use crate::ConcreteNumber;
+
+/// How the magnitude of a [`ConcreteNumber`] is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    /// `1500 W`
+    #[default]
+    Plain,
+    /// `1.5e3 W`
+    Scientific,
+    /// Scientific notation with a power of ten that is a multiple of three,
+    /// to match the SI prefixes, e.g. `15e-6 A` rather than `1.5e-5 A`.
+    Engineering,
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" | "decimal" => Ok(Notation::Plain),
+            "scientific" | "sci" => Ok(Notation::Scientific),
+            "engineering" | "eng" => Ok(Notation::Engineering),
+            _ => Err(format!("Unknown notation: {s}")),
+        }
+    }
+}
+
+/// A concrete number written in a chosen notation; see
+/// [`ConcreteNumber::in_notation`]. Precision, width and sign flags apply to
+/// the magnitude, so `{:+.2}` writes `+1.50e3 W` in engineering notation.
+#[derive(Debug, Clone, Copy)]
+pub struct NotationDisplay<'a> {
+    pub(crate) number: &'a ConcreteNumber,
+    pub(crate) notation: Notation,
+    pub(crate) upper: bool,
+}
+
+impl NotationDisplay<'_> {
+    /// Writes the exponent marker as `E` rather than `e`, e.g. `15E-6 A`.
+    pub fn uppercase(mut self) -> Self {
+        self.upper = true;
+        self
+    }
+}
+
+impl Display for NotationDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let magnitude = self.number.displayed_magnitude();
+        write_magnitude(f, magnitude, self.notation, self.upper)?;
+        self.number.write_unit(f)
+    }
+}
+
+/// Writes `magnitude` in `notation`, honouring the formatter's flags. `upper`
+/// writes the exponent marker as `E` rather than `e`.
+fn write_magnitude(
+    f: &mut Formatter<'_>,
+    magnitude: f64,
+    notation: Notation,
+    upper: bool,
+) -> fmt::Result {
+    match notation {
+        Notation::Plain => Display::fmt(&magnitude, f),
+        Notation::Scientific if upper => UpperExp::fmt(&magnitude, f),
+        Notation::Scientific => LowerExp::fmt(&magnitude, f),
+        Notation::Engineering if magnitude == 0.0 || !magnitude.is_finite() => {
+            Display::fmt(&magnitude, f)
+        }
+        Notation::Engineering => write_engineering(f, magnitude, upper),
+    }
+}
+
+/// Splits `magnitude` into a mantissa between 1 and 1000 and a power of ten
+/// that is a multiple of three.
+fn engineering_parts(magnitude: f64) -> (f64, i32) {
+    let mut exponent = (magnitude.abs().log10() / 3.0).floor() as i32 * 3;
+    let scale = |exponent: i32| match exponent >= 0 {
+        true => magnitude / 10f64.powi(exponent),
+        false => magnitude * 10f64.powi(-exponent),
+    };
+    let mut mantissa = scale(exponent);
+    // log10 can land on the wrong side of a power of 1000.
+    if mantissa.abs() >= 1000.0 {
+        exponent += 3;
+        mantissa = scale(exponent);
+    } else if mantissa.abs() < 1.0 {
+        exponent -= 3;
+        mantissa = scale(exponent);
+    }
+    (mantissa, exponent)
+}
+
+fn write_engineering(f: &mut Formatter<'_>, magnitude: f64, upper: bool) -> fmt::Result {
+    let (mut mantissa, mut exponent) = engineering_parts(magnitude);
+    let round = |mantissa: f64| match f.precision() {
+        Some(precision) => format!("{:.*}", precision, mantissa.abs()),
+        None => mantissa.abs().to_string(),
+    };
+    let mut digits = round(mantissa);
+    // Rounding can carry into the next power of 1000, e.g. 999.96 to 1000.0.
+    if digits.parse::<f64>().is_ok_and(|rounded| rounded >= 1000.0) {
+        mantissa /= 1000.0;
+        exponent += 3;
+        digits = round(mantissa);
+    }
+    let sign = match (mantissa < 0.0, f.sign_plus()) {
+        (true, _) => "-",
+        (false, true) => "+",
+        (false, false) => "",
+    };
+    let marker = if upper { 'E' } else { 'e' };
+    pad(f, sign, &format!("{digits}{marker}{exponent}"))
+}
+
+/// Writes a number padded to the formatter's width. Like the standard
+/// numeric types, it aligns right by default and `0` pads after the sign.
+fn pad(f: &mut Formatter<'_>, sign: &str, digits: &str) -> fmt::Result {
+    let length = sign.chars().count() + digits.chars().count();
+    let padding = f.width().unwrap_or(0).saturating_sub(length);
+    if f.sign_aware_zero_pad() {
+        return write!(f, "{sign}{}{digits}", "0".repeat(padding));
+    }
+    let (before, after) = match f.align() {
+        Some(Alignment::Left) => (0, padding),
+        Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+        Some(Alignment::Right) | None => (padding, 0),
+    };
+    let fill = f.fill().to_string();
+    write!(
+        f,
+        "{}{sign}{digits}{}",
+        fill.repeat(before),
+        fill.repeat(after)
+    )
+}
@@ -0,0 +1,275 @@
+//! How the CLI writes results and errors for scripts to read.
+
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+use unit_parser::{
+    notation::Notation, rational::Rational, units::UnitSystem, ConcreteNumber, CustomError,
+    ErrorCategory,
+};
+
+/// How results are shown as text: in which system of units, whether in the
+/// best prefixed unit or exactly as coherent SI, and how the magnitude is
+/// written. JSON and CSV ignore these; see [`DisplayOptions::for_format`].
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayOptions {
+    pub system: UnitSystem,
+    pub best_unit: bool,
+    pub notation: Notation,
+    /// Digits after the decimal point, or as many as it takes if `None`.
+    pub precision: Option<usize>,
+}
+
+impl DisplayOptions {
+    /// The options to show results in `format` with. JSON and CSV are always
+    /// in coherent SI at full precision, unless the expression converted to a
+    /// unit itself, so that scripts can read the magnitude alongside the
+    /// dimensions.
+    pub fn for_format(self, format: OutputFormat) -> DisplayOptions {
+        match format {
+            OutputFormat::Text => self,
+            OutputFormat::Json | OutputFormat::Csv => DisplayOptions {
+                system: UnitSystem::SI,
+                best_unit: false,
+                notation: Notation::Plain,
+                precision: None,
+            },
+        }
+    }
+
+    /// Converts a result to the unit it is shown in. Scientific and
+    /// engineering notation already scale the magnitude, so they skip the
+    /// best prefixed unit.
+    pub fn present(self, result: ConcreteNumber) -> ConcreteNumber {
+        let result = result.in_unit_system(self.system);
+        match self.best_unit && self.notation == Notation::Plain {
+            true => result.with_best_unit(),
+            false => result,
+        }
+    }
+
+    /// Writes a presented result as text.
+    pub fn format(self, result: &ConcreteNumber) -> String {
+        let result = result.in_notation(self.notation);
+        match self.precision {
+            Some(precision) => format!("{:.*}", precision, result),
+            None => result.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    /// A JSON object per result; a JSON Lines stream in batch mode.
+    Json,
+    /// Comma-separated values, with a header row.
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" | "jsonl" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!("Unknown output format: {s}")),
+        }
+    }
+}
+
+/// The exit code for a failed evaluation: 3 for input that doesn't parse, 4
+/// for a dimension mismatch and 1 for anything else.
+pub fn exit_code(error: &CustomError) -> i32 {
+    match error.category() {
+        ErrorCategory::Parse => 3,
+        ErrorCategory::Dimension => 4,
+        ErrorCategory::Evaluation => 1,
+    }
+}
+
+/// An exponent as a JSON number, or as a string such as `"1/2"` if it is
+/// fractional.
+fn exponent(exponent: Rational) -> Value {
+    match exponent.is_integer() {
+        true => json!(exponent.numerator()),
+        false => json!(exponent.to_string()),
+    }
+}
+
+/// A magnitude as a JSON number, or as the string `"inf"`, `"-inf"` or
+/// `"NaN"`, which JSON has no numbers for.
+fn magnitude(magnitude: f64) -> Value {
+    match magnitude.is_finite() {
+        true => json!(magnitude),
+        false => json!(magnitude.to_string()),
+    }
+}
+
+pub fn json_result(input: &str, result: &ConcreteNumber) -> Value {
+    let physical_quantity = result.physical_quantity();
+    json!({
+        "input": input,
+        "magnitude": magnitude(result.displayed_magnitude()),
+        "unit": result.unit_symbol(),
+        "dimensions": {
+            "time": exponent(physical_quantity.time()),
+            "length": exponent(physical_quantity.length()),
+            "mass": exponent(physical_quantity.mass()),
+            "current": exponent(physical_quantity.current()),
+            "temperature": exponent(physical_quantity.temperature()),
+            "amount_of_substance": exponent(physical_quantity.amount_of_substance()),
+            "luminous_intensity": exponent(physical_quantity.luminous_intensity()),
+            "angle": exponent(physical_quantity.angle()),
+        },
+        "kind": physical_quantity.kind(),
+    })
+}
+
+pub fn json_error(input: &str, error: &CustomError) -> Value {
+    let category = match error.category() {
+        ErrorCategory::Parse => "parse",
+        ErrorCategory::Dimension => "dimension",
+        ErrorCategory::Evaluation => "evaluation",
+    };
+    let span = error
+        .span()
+        .map(|span| json!({ "start": span.start(), "end": span.end() }));
+    json!({
+        "input": input,
+        "error": {
+            "category": category,
+            "message": error.to_string(),
+            "span": span,
+        },
+    })
+}
+
+/// One line of text output for a batch: the input and its result or error.
+pub fn text_line(
+    input: &str,
+    result: &Result<ConcreteNumber, CustomError>,
+    options: DisplayOptions,
+) -> String {
+    match result {
+        Ok(result) => format!("{} => {}", input, options.format(result)),
+        Err(error) => format!("{} => ERROR: {}", input, error),
+    }
+}
+
+pub const CSV_HEADER: &str = "input,magnitude,unit,kind,error";
+
+/// Quotes a CSV field if it needs it, doubling any quotes inside.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// One CSV row, in the columns of [`CSV_HEADER`]. Failed rows have only an
+/// input and an error.
+pub fn csv_row(input: &str, result: &Result<ConcreteNumber, CustomError>) -> String {
+    let fields = match result {
+        Ok(result) => [
+            input.to_owned(),
+            result.displayed_magnitude().to_string(),
+            result.unit_symbol(),
+            result.physical_quantity().kind(),
+            String::new(),
+        ],
+        Err(error) => [
+            input.to_owned(),
+            String::new(),
+            String::new(),
+            String::new(),
+            error.to_string(),
+        ],
+    };
+    fields.map(|field| csv_field(&field)).join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use unit_parser::{evaluate, notation::Notation, units::UnitSystem};
+
+    use super::{csv_field, exit_code, json_error, json_result, DisplayOptions};
+
+    #[test]
+    fn csv_fields_are_quoted_when_they_need_to_be() {
+        assert_eq!(csv_field("3 m"), "3 m");
+        assert_eq!(csv_field("max(1, 2)"), "\"max(1, 2)\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn json_results_carry_dimensions_and_kind() {
+        let options = DisplayOptions {
+            system: UnitSystem::SI,
+            best_unit: false,
+            notation: Notation::Plain,
+            precision: None,
+        };
+        let result = options.present(evaluate("(4 Hz)^(1/2) * 3 m").unwrap());
+        let json = json_result("(4 Hz)^(1/2) * 3 m", &result);
+        assert_eq!(json["magnitude"], 6.0);
+        assert_eq!(json["dimensions"]["time"], "-1/2");
+        assert_eq!(json["dimensions"]["length"], 1);
+        assert_eq!(json["dimensions"]["mass"], 0);
+        assert_eq!(json["kind"], result.physical_quantity().kind());
+    }
+
+    #[test]
+    fn json_magnitudes_that_are_not_numbers_are_strings() {
+        let result = evaluate("ln(0)").unwrap();
+        assert_eq!(json_result("ln(0)", &result)["magnitude"], "-inf");
+        let result = evaluate("1 m / 0").unwrap();
+        assert_eq!(json_result("1 m / 0", &result)["magnitude"], "inf");
+    }
+
+    #[test]
+    fn json_errors_carry_category_message_and_span() {
+        let error = evaluate("3 furlongs").unwrap_err();
+        assert_eq!(
+            json_error("3 furlongs", &error),
+            json!({
+                "input": "3 furlongs",
+                "error": {
+                    "category": "parse",
+                    "message": "unknown unit `furlongs`",
+                    "span": { "start": 2, "end": 10 },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn exit_codes_follow_the_error_category() {
+        let code = |input| exit_code(&evaluate(input).unwrap_err());
+        assert_eq!(code("3 m +"), 3);
+        assert_eq!(code("1 m + 1 s"), 4);
+        assert_eq!(code("20 °C * 2"), 1);
+    }
+
+    #[test]
+    fn scientific_notation_skips_the_best_unit() {
+        let options = |notation| DisplayOptions {
+            system: UnitSystem::SI,
+            best_unit: true,
+            notation,
+            precision: None,
+        };
+        let show = |notation, input| {
+            let options = options(notation);
+            options.format(&options.present(evaluate(input).unwrap()))
+        };
+        assert_eq!(show(Notation::Plain, "1500 W"), "1.5 kW");
+        assert_eq!(show(Notation::Scientific, "1500 W"), "1.5e3 W");
+        assert_eq!(show(Notation::Engineering, "0.000015 A"), "15e-6 A");
+    }
+}
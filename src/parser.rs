@@ -2,146 +2,369 @@ use std::fmt::{self, Debug, Display, Formatter};
 
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while1},
-    character::{
-        complete::{char, digit1, multispace0, multispace1},
-        is_alphabetic,
-    },
-    combinator::{map, map_res, opt, recognize},
-    error::ParseError,
-    multi::{many0, separated_list0},
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::{char, digit1, multispace0, multispace1, one_of, satisfy},
+    combinator::{consumed, map, map_opt, map_res, opt, peek, recognize, verify},
+    error::{ErrorKind, FromExternalError, ParseError},
+    multi::{many0, separated_list0, separated_list1},
     number::complete::double,
-    sequence::{delimited, pair, preceded, separated_pair},
+    sequence::{delimited, pair, preceded},
     IResult, Parser,
 };
+use nom_locate::LocatedSpan;
 
 use crate::{
-    ConcreteNumber, ConcreteNumberBuilder, CustomError, PhysicalQuantity, PhysicalQuantityBuilder,
+    constants,
+    functions::{self, Function},
+    rational::Rational,
+    units, ConcreteNumber, ConcreteNumberBuilder, CustomError, DisplayUnit, Environment,
+    PhysicalQuantity, PhysicalQuantityBuilder, SourceSpan, SUPERSCRIPT_DIGITS,
 };
 
+/// Parser input, which keeps track of its byte offset into the original text.
+pub type Span<'a> = LocatedSpan<&'a str>;
+
+fn source_span(span: Span<'_>) -> SourceSpan {
+    SourceSpan::new(
+        span.location_offset(),
+        span.location_offset() + span.fragment().len(),
+    )
+}
+
+/// The error type threaded through every parser. nom's own errors are kept as
+/// they are; an unknown unit or function is raised as a `nom::Err::Failure`
+/// so that a misspelling aborts the parse instead of being backtracked over.
+#[derive(Debug, PartialEq)]
+pub enum ParseFailure<I> {
+    UnknownUnit(I),
+    UnknownFunction(I),
+    /// A power written after a number with a unit, as in `2 m ^ 2`, where it
+    /// isn't clear whether the number or only the unit is raised.
+    AmbiguousPower(I, Rational),
+    /// A unit whose dimensions have exponents too large to represent.
+    ExponentOutOfRange(I),
+    Nom(I, ErrorKind),
+}
+
+impl<I> ParseError<I> for ParseFailure<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        ParseFailure::Nom(input, kind)
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<I, E> FromExternalError<I, E> for ParseFailure<I> {
+    fn from_external_error(input: I, kind: ErrorKind, _: E) -> Self {
+        ParseFailure::Nom(input, kind)
+    }
+}
+
+type PResult<'a, O> = IResult<Span<'a>, O, ParseFailure<Span<'a>>>;
+
 fn alphabet_char(c: char) -> bool {
-    is_alphabetic(c as u8)
+    c.is_alphabetic() || c == '°'
 }
 
-pub fn word(input: &str) -> IResult<&str, &str> {
+pub fn word(input: Span<'_>) -> PResult<'_, Span<'_>> {
     take_while1(alphabet_char)(input)
 }
 
-fn parse_isize(input: &str) -> IResult<&str, isize> {
-    let (i, number) = map_res(recognize(preceded(opt(tag("-")), digit1)), |s| {
-        isize::from_str_radix(s, 10)
-    })(input)?;
+/// Words with a meaning in the expression grammar, which therefore can't be
+/// read as unit symbols or variable names.
+const KEYWORDS: [&str; 3] = ["to", "in", "let"];
+
+fn unit_word(input: Span<'_>) -> PResult<'_, Span<'_>> {
+    verify(word, |w: &Span| !KEYWORDS.contains(w.fragment()))(input)
+}
+
+/// A variable name: a letter or underscore, then letters, digits and
+/// underscores.
+fn identifier(input: Span<'_>) -> PResult<'_, Span<'_>> {
+    recognize(pair(
+        satisfy(|c| c.is_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_alphanumeric() || c == '_'),
+    ))(input)
+}
+
+fn keyword<'a>(keyword: &'static str) -> impl FnMut(Span<'a>) -> PResult<'a, Span<'a>> {
+    verify(identifier, move |w: &Span| *w.fragment() == keyword)
+}
+
+fn parse_integer(input: Span<'_>) -> PResult<'_, i16> {
+    let (i, number) = map_res(
+        pair(opt(alt((tag("-"), tag("−")))), digit1),
+        |(sign, digits): (Option<Span>, Span)| {
+            digits
+                .fragment()
+                .parse::<i16>()
+                .map(|n| if sign.is_some() { -n } else { n })
+        },
+    )(input)?;
 
     Ok((i, number))
 }
 
-pub fn unit_as_tuple(input: &str) -> IResult<&str, (&str, isize)> {
+fn superscript_digit(c: char) -> bool {
+    SUPERSCRIPT_DIGITS.contains(&c)
+}
+
+/// An exponent written in Unicode superscripts, e.g. the `⁻¹` in `s⁻¹`.
+fn parse_superscript_integer(input: Span<'_>) -> PResult<'_, i16> {
+    map_opt(
+        pair(opt(char('⁻')), take_while1(superscript_digit)),
+        |(sign, digits): (Option<char>, Span)| {
+            let n = digits.fragment().chars().try_fold(0i16, |acc, c| {
+                let digit = SUPERSCRIPT_DIGITS.iter().position(|&d| d == c).unwrap();
+                acc.checked_mul(10)?.checked_add(digit as i16)
+            })?;
+            if sign.is_some() {
+                Some(-n)
+            } else {
+                Some(n)
+            }
+        },
+    )(input)
+}
+
+fn parse_denominator(input: Span<'_>) -> PResult<'_, i16> {
+    verify(
+        map_res(digit1, |digits: Span| digits.fragment().parse::<i16>()),
+        |denominator| *denominator != 0,
+    )(input)
+}
+
+fn parse_superscript_denominator(input: Span<'_>) -> PResult<'_, i16> {
+    verify(
+        preceded(char('ᐟ'), parse_superscript_integer),
+        |denominator| *denominator > 0,
+    )(input)
+}
+
+/// An integer or a fraction such as `-1/2`.
+fn parse_rational(input: Span<'_>) -> PResult<'_, Rational> {
+    map(
+        pair(parse_integer, opt(preceded(tag("/"), parse_denominator))),
+        |(numerator, denominator)| Rational::new(numerator, denominator.unwrap_or(1)),
+    )(input)
+}
+
+/// An integer, or a fraction that isn't a whole number. Without brackets the
+/// slash only belongs to the exponent when it makes a true fraction, so
+/// `m^-1/2` is a square root but `3 m^2/2` is `3 m^2` divided by 2.
+fn bare_rational(input: Span<'_>) -> PResult<'_, Rational> {
+    let (input, numerator) = parse_integer(input)?;
+    let (input, denominator) = opt(verify(
+        preceded(tag("/"), parse_denominator),
+        |denominator| !Rational::new(numerator, *denominator).is_integer(),
+    ))(input)?;
+    Ok((input, Rational::new(numerator, denominator.unwrap_or(1))))
+}
+
+/// The power a unit is raised to: `^2`, `**2` or `²`, or a fraction written
+/// `^-1/2`, `^(-1/2)` or `⁻¹ᐟ²`.
+fn exponent(input: Span<'_>) -> PResult<'_, Rational> {
     alt((
-        separated_pair(word, char('^'), parse_isize),
-        map(word, |s: &str| (s, 1isize)),
+        preceded(
+            alt((tag("^"), tag("**"))),
+            alt((
+                delimited(char('('), parse_rational, char(')')),
+                bare_rational,
+            )),
+        ),
+        map(
+            pair(
+                parse_superscript_integer,
+                opt(parse_superscript_denominator),
+            ),
+            |(numerator, denominator)| Rational::new(numerator, denominator.unwrap_or(1)),
+        ),
     ))(input)
-    .or(Ok((input, ("dimensionless", 1isize))))
-}
-
-fn unit_as_physical_quantity(input: &str) -> IResult<&str, PhysicalQuantity> {
-    map(unit_as_tuple, |(s, i)| {
-        let pq = PhysicalQuantityBuilder::new();
-        match s {
-            "s" => pq.time(i).build(),
-            "m" => pq.length(i).build(),
-            "kg" => pq.mass(i).build(),
-            "A" => pq.current(i).build(),
-            "K" => pq.temperature(i).build(),
-            "mol" => pq.amount_of_substance(i).build(),
-            "cd" => pq.luminous_intensity(i).build(),
-            "Hz" => pq.time(i * -1).build(),
-            "N" => pq.time(i * -2).length(i).mass(i).build(),
-            "Pa" => pq.time(i * -2).length(-1 * i).mass(i).build(),
-            "J" => pq.time(i * -2).length(2 * i).mass(i).build(),
-            "W" => pq.time(i * -3).length(2 * i).mass(i).build(),
-            "C" => pq.time(i).current(i).build(),
-            "V" => pq
-                .time(i * -3)
-                .length(i * 2)
-                .mass(i)
-                .current(i * -1)
-                .build(),
-            "Wb" => pq
-                .time(i * -2)
-                .length(i * 2)
-                .mass(i)
-                .current(i * -1)
-                .build(),
-            "T" => pq.time(i * -2).mass(i).current(i * -1).build(),
-            "F" => pq
-                .time(i * 4)
-                .length(i * -2)
-                .mass(i * -1)
-                .current(i * 2)
-                .build(),
-            "ohm" | "Ω" => pq
-                .time(i * -3)
-                .length(i * 2)
-                .mass(i)
-                .current(i * -2)
-                .build(),
-            "S" => pq
-                .time(i * 3)
-                .length(i * -2)
-                .mass(i * -1)
-                .current(i * 2)
-                .build(),
-            "H" => pq
-                .time(i * -2)
-                .length(i * 2)
-                .mass(i)
-                .current(i * -2)
-                .build(),
-            "kat" => pq.time(i * -1).amount_of_substance(i).build(),
-            "dimensionless" => pq.build(),
-            // dimensionless for now—should really be an error
-            _ => pq.build(),
+}
+
+pub fn unit_as_tuple(input: Span<'_>) -> PResult<'_, (Span<'_>, Rational)> {
+    alt((
+        pair(unit_word, exponent),
+        map(unit_word, |s: Span| (s, Rational::ONE)),
+    ))(input)
+}
+
+/// Raises the size of a unit to `exponent`, exactly for integer powers.
+fn scale_pow(scale: f64, exponent: Rational) -> f64 {
+    match exponent.is_integer() {
+        true => scale.powi(exponent.numerator().into()),
+        false => scale.powf(exponent.to_f64()),
+    }
+}
+
+/// Fails the parse at `input` because a unit's exponents are out of range.
+fn exponent_out_of_range(input: Span<'_>) -> nom::Err<ParseFailure<Span<'_>>> {
+    nom::Err::Failure(ParseFailure::ExponentOutOfRange(input))
+}
+
+fn unit_as_physical_quantity(input: Span<'_>) -> PResult<'_, (f64, PhysicalQuantity)> {
+    let (remainder, (s, i)) = unit_as_tuple(input)?;
+    match units::resolve(s.fragment()) {
+        Some((factor, pq)) => {
+            let pq = pq.checked_pow(i).ok_or_else(|| exponent_out_of_range(s))?;
+            Ok((remainder, (scale_pow(factor, i), pq)))
         }
-    })(input)
+        None => Err(nom::Err::Failure(ParseFailure::UnknownUnit(s))),
+    }
 }
 
-fn units(input: &str) -> IResult<&str, Vec<PhysicalQuantity>> {
-    separated_list0(multispace1, unit_as_physical_quantity)(input)
+/// A parenthesised unit expression, optionally raised to a power as a whole.
+fn unit_group(input: Span<'_>) -> PResult<'_, (f64, PhysicalQuantity)> {
+    let (input, (scale, pq)) = delimited(
+        pair(char('('), multispace0),
+        unit_expression,
+        pair(multispace0, char(')')),
+    )(input)?;
+    let (input, i) = opt(exponent)(input)?;
+    let i = i.unwrap_or(Rational::ONE);
+    let pq = pq
+        .checked_pow(i)
+        .ok_or_else(|| exponent_out_of_range(input))?;
+    Ok((input, (scale_pow(scale, i), pq)))
 }
 
-fn combined_unit(input: &str) -> IResult<&str, PhysicalQuantity> {
-    let units = units(input)?;
-    let combined_unit = units
-        .1
-        .iter()
-        .fold(PhysicalQuantityBuilder::new().build(), |acc, &pq| acc * pq);
-    Ok((units.0, combined_unit))
+fn unit_power(input: Span<'_>) -> PResult<'_, (f64, PhysicalQuantity)> {
+    alt((unit_as_physical_quantity, unit_group))(input)
 }
 
-pub fn get_concrete_number_as_tuple(input: &str) -> IResult<&str, (f64, PhysicalQuantity)> {
-    pair(ws(double), ws(combined_unit))(input)
+/// Units multiplied together, written side by side (`N m`) or joined by `*`,
+/// `·` or `.` (`N*m`, `N·m`, `N.m`). `*` and `.` must not have spaces around
+/// them, so that `3 W * 2 m` is still read as a product of two numbers.
+fn unit_product(input: Span<'_>) -> PResult<'_, (f64, PhysicalQuantity)> {
+    let (input, initial) = unit_power(input)?;
+    let (input, remainder) = many0(preceded(
+        alt((ws(tag("·")), multispace1, tag("*"), tag("."))),
+        unit_power,
+    ))(input)?;
+    let product = remainder
+        .into_iter()
+        .try_fold(initial, |(acc_scale, acc_pq), (scale, pq)| {
+            Some((acc_scale * scale, acc_pq.checked_mul(pq)?))
+        })
+        .ok_or_else(|| exponent_out_of_range(input))?;
+    Ok((input, product))
+}
+
+/// A full unit expression such as `kg/(m s^2)`. Products bind more tightly
+/// than `/`, so `J/mol K` is `J/(mol K)`, and like `*` the `/` must not have
+/// spaces around it.
+fn unit_expression(input: Span<'_>) -> PResult<'_, (f64, PhysicalQuantity)> {
+    let (input, initial) = unit_product(input)?;
+    let (input, remainder) = many0(preceded(tag("/"), unit_product))(input)?;
+    let quotient = remainder
+        .into_iter()
+        .try_fold(initial, |(acc_scale, acc_pq), (scale, pq)| {
+            Some((acc_scale / scale, acc_pq.checked_div(pq)?))
+        })
+        .ok_or_else(|| exponent_out_of_range(input))?;
+    Ok((input, quotient))
+}
+
+/// The unit after a magnitude, or dimensionless if there isn't one.
+fn combined_unit(input: Span<'_>) -> PResult<'_, (f64, PhysicalQuantity)> {
+    map(opt(unit_expression), |unit| {
+        unit.unwrap_or((1.0, PhysicalQuantityBuilder::new().build()))
+    })(input)
 }
 
- fn concrete_number(input: &str) -> IResult<&str, ConcreteNumber> {
+/// Parses a magnitude and its unit. A unit that is nothing but an affine
+/// temperature scale, as in `20 °C`, gives an absolute temperature; any other
+/// unit gives the magnitude converted to coherent SI (so `5 km` is `5000 m`).
+/// The magnitude is unsigned and starts with a digit or `.`, so that names
+/// such as `inflow` or `nano` aren't read as `inf` or `nan`; a leading sign
+/// is a prefix operator.
+fn concrete_number(input: Span<'_>) -> PResult<'_, Expr> {
+    let (input, ((number, magnitude), (symbol, (scale, physical_quantity)))) = pair(
+        consumed(preceded(
+            peek(satisfy(|c| c.is_ascii_digit() || c == '.')),
+            double,
+        )),
+        preceded(multispace0, consumed(combined_unit)),
+    )(input)?;
+    let value = match units::affine(symbol.fragment()) {
+        Some(unit) => ConcreteNumberBuilder::new()
+            .magnitude((magnitude + unit.offset) * unit.factor)
+            .physical_quantity(unit.physical_quantity)
+            .display_unit(Some(DisplayUnit::new(
+                unit.symbol,
+                unit.factor,
+                unit.offset,
+                unit.physical_quantity,
+            )))
+            .absolute_temperature(true)
+            .build(),
+        None => ConcreteNumberBuilder::new()
+            .magnitude(magnitude * scale)
+            .physical_quantity(physical_quantity)
+            .build(),
+    };
+    let span = match symbol.fragment().is_empty() {
+        true => source_span(number),
+        false => SourceSpan::new(number.location_offset(), source_span(symbol).end),
+    };
+    Ok((input, Expr::new(ExprKind::Value(value), span)))
+}
+
+/// The unit after `to` or `in` in a conversion, keeping the text the user
+/// wrote so the result can be shown in it.
+fn conversion_target(input: Span<'_>) -> PResult<'_, (DisplayUnit, SourceSpan)> {
     map(
-        get_concrete_number_as_tuple,
-        |(magnitude, physical_quantity)| {
-            ConcreteNumberBuilder::new()
-                .magnitude(magnitude)
-                .physical_quantity(physical_quantity)
-                .build()
+        preceded(
+            ws(alt((tag("to"), tag("in")))),
+            verify(consumed(combined_unit), |(symbol, _): &(Span, _)| {
+                !symbol.fragment().is_empty()
+            }),
+        ),
+        |(symbol, (scale, physical_quantity))| {
+            let offset = units::affine(symbol.fragment()).map_or(0.0, |unit| unit.offset);
+            (
+                DisplayUnit::new(symbol.fragment(), scale, offset, physical_quantity),
+                source_span(symbol),
+            )
         },
     )(input)
 }
 
+/// A node in the expression tree, with the byte range of the input it was
+/// parsed from.
+#[derive(Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: SourceSpan,
+}
+
 #[derive(Clone)]
-pub enum Expr {
+pub enum ExprKind {
     Value(ConcreteNumber),
     Add(Box<Expr>, Box<Expr>),
     Sub(Box<Expr>, Box<Expr>),
     Mul(Box<Expr>, Box<Expr>),
     Div(Box<Expr>, Box<Expr>),
     Paren(Box<Expr>),
+    /// A prefix `-`, which binds more tightly than `*` and `/` but less
+    /// tightly than `^`, so `-2^2` is `-4`.
+    Neg(Box<Expr>),
+    /// A prefix `+`, which leaves its operand as it is.
+    Plus(Box<Expr>),
+    /// A power, written `x^2` or `x^(1/2)`.
+    Pow(Box<Expr>, Rational),
+    /// A function applied to its arguments, e.g. `sqrt(x)` or `max(a, b)`.
+    Call(Function, Vec<Expr>),
+    Convert(Box<Expr>, DisplayUnit),
+    /// A variable or named constant, including `ans` (also written `_`) for
+    /// the previous result.
+    Variable(String),
+    /// `let name = value`, which binds the variable and evaluates to its value.
+    Let(String, Box<Expr>),
+    /// Statements separated by `;`, evaluating to the last of them.
+    Sequence(Vec<Expr>),
 }
 
 #[derive(Debug)]
@@ -153,157 +376,476 @@ pub enum Oper {
 }
 
 impl Expr {
-    pub fn evaluate(self) -> Result<ConcreteNumber, CustomError> {
-        match self {
-            Expr::Value(concrete_number) => Ok(concrete_number),
-            Expr::Add(expr_a, expr_b) => {
-                let expr_a = expr_a.clone().evaluate();
-                let expr_b = expr_b.clone().evaluate();
-                if expr_a.is_ok() && expr_b.is_ok() {
-                    expr_a.unwrap() + expr_b.unwrap()
-                } else {
-                    return Err(CustomError::SubExpressionError);
-                }
+    fn new(kind: ExprKind, span: SourceSpan) -> Self {
+        Self { kind, span }
+    }
+
+    /// Evaluates the expression, reading and binding variables in
+    /// `environment`. When an operation fails, the error is wrapped with the
+    /// expression it failed in, and again for each enclosing operation, so the
+    /// error carries a trail back to the top.
+    pub fn evaluate(&self, environment: &mut Environment) -> Result<ConcreteNumber, CustomError> {
+        let result = match &self.kind {
+            ExprKind::Value(concrete_number) => {
+                return Ok(environment.normalize_angles(concrete_number.clone()))
             }
-            Expr::Sub(expr_a, expr_b) => {
-                let expr_a = expr_a.clone().evaluate();
-                let expr_b = expr_b.clone().evaluate();
-                if expr_a.is_ok() && expr_b.is_ok() {
-                    expr_a.unwrap() - expr_b.unwrap()
-                } else {
-                    return Err(CustomError::SubExpressionError);
+            ExprKind::Paren(expression) | ExprKind::Plus(expression) => {
+                return expression.evaluate(environment)
+            }
+            ExprKind::Neg(expression) => return expression.evaluate(environment).map(|v| -v),
+            ExprKind::Variable(name) => {
+                return match lookup(name, environment) {
+                    Some(value) => Ok(environment.normalize_angles(value)),
+                    None if name == "ans" => Err(CustomError::NoPreviousResult { span: self.span }),
+                    None => Err(CustomError::UnknownVariable {
+                        name: name.clone(),
+                        span: self.span,
+                    }),
                 }
             }
-            Expr::Mul(expr_a, expr_b) => {
-                let expr_a = expr_a.clone().evaluate();
-                let expr_b = expr_b.clone().evaluate();
-                if expr_a.is_ok() && expr_b.is_ok() {
-                    Ok(expr_a.unwrap() * expr_b.unwrap())
-                } else {
-                    return Err(CustomError::SubExpressionError);
+            ExprKind::Let(name, expression) => {
+                let value = expression.evaluate(environment)?;
+                environment.set(name, value.clone());
+                return Ok(value);
+            }
+            ExprKind::Sequence(statements) => {
+                let mut value = None;
+                for statement in statements {
+                    value = Some(statement.evaluate(environment)?);
                 }
+                return Ok(value.expect("a sequence has at least one statement"));
+            }
+            ExprKind::Add(expr_a, expr_b) => {
+                operands(expr_a, expr_b, environment).and_then(|(a, b)| a + b)
+            }
+            ExprKind::Sub(expr_a, expr_b) => {
+                operands(expr_a, expr_b, environment).and_then(|(a, b)| a - b)
+            }
+            ExprKind::Mul(expr_a, expr_b) => {
+                operands(expr_a, expr_b, environment).and_then(|(a, b)| a * b)
             }
-            Expr::Div(expr_a, expr_b) => {
-                let expr_a = expr_a.clone().evaluate();
-                let expr_b = expr_b.clone().evaluate();
-                if expr_a.is_ok() && expr_b.is_ok() {
-                    Ok(expr_a.unwrap() / expr_b.unwrap())
-                } else {
-                    return Err(CustomError::SubExpressionError);
+            ExprKind::Div(expr_a, expr_b) => {
+                operands(expr_a, expr_b, environment).and_then(|(a, b)| a / b)
+            }
+            ExprKind::Pow(expression, exponent) => expression
+                .evaluate(environment)
+                .and_then(|value| value.pow(*exponent)),
+            ExprKind::Call(function, arguments) => arguments
+                .iter()
+                .map(|argument| argument.evaluate(environment))
+                .collect::<Result<Vec<_>, _>>()
+                .and_then(|arguments| function.apply(arguments))
+                .map(|value| environment.normalize_angles(value)),
+            ExprKind::Convert(expression, unit) => {
+                expression.evaluate(environment).and_then(|value| {
+                    match environment.tracks_angles() {
+                        true => value.convert_to(unit.clone()),
+                        false => value.convert_to(unit.clone().without_angle()),
+                    }
+                })
+            }
+        };
+        result.map_err(|source| {
+            let variables = match source {
+                CustomError::InSubExpression { .. } => vec![],
+                _ => self.variables(environment),
+            };
+            CustomError::InSubExpression {
+                expression: self.to_string(),
+                span: self.span,
+                variables,
+                source: Box::new(source),
+            }
+        })
+    }
+
+    /// The variables and constants used in this expression that have values,
+    /// with their physical quantities, in the order they first appear.
+    fn variables(&self, environment: &Environment) -> Vec<(String, PhysicalQuantity)> {
+        let mut variables: Vec<(String, PhysicalQuantity)> = vec![];
+        self.visit(&mut |expression| {
+            if let ExprKind::Variable(name) = &expression.kind {
+                if let Some(value) = lookup(name, environment) {
+                    if !variables.iter().any(|(seen, _)| seen == name) {
+                        variables.push((name.clone(), value.physical_quantity()));
+                    }
                 }
             }
-            Expr::Paren(expression) => expression.clone().evaluate(),
+        });
+        variables
+    }
+
+    /// Calls `f` with this expression and every expression within it.
+    fn visit(&self, f: &mut impl FnMut(&Expr)) {
+        f(self);
+        match &self.kind {
+            ExprKind::Add(a, b)
+            | ExprKind::Sub(a, b)
+            | ExprKind::Mul(a, b)
+            | ExprKind::Div(a, b) => {
+                a.visit(f);
+                b.visit(f);
+            }
+            ExprKind::Paren(expression)
+            | ExprKind::Neg(expression)
+            | ExprKind::Plus(expression)
+            | ExprKind::Pow(expression, _)
+            | ExprKind::Convert(expression, _)
+            | ExprKind::Let(_, expression) => expression.visit(f),
+            ExprKind::Sequence(statements) | ExprKind::Call(_, statements) => {
+                statements.iter().for_each(|statement| statement.visit(f))
+            }
+            ExprKind::Value(_) | ExprKind::Variable(_) => {}
         }
     }
 }
 
+/// The value of a variable, or failing that of the constant with that name,
+/// so a variable can shadow a constant.
+fn lookup(name: &str, environment: &Environment) -> Option<ConcreteNumber> {
+    environment.get(name).cloned().or_else(|| {
+        constants::lookup(name)
+            .map(|constant| ConcreteNumber::new(constant.value, constant.physical_quantity))
+    })
+}
+
+fn operands(
+    expr_a: &Expr,
+    expr_b: &Expr,
+    environment: &mut Environment,
+) -> Result<(ConcreteNumber, ConcreteNumber), CustomError> {
+    Ok((expr_a.evaluate(environment)?, expr_b.evaluate(environment)?))
+}
+
 impl Display for Expr {
     fn fmt(&self, format: &mut Formatter<'_>) -> fmt::Result {
-        use self::Expr::*;
-        match *self {
-            Value(val) => write!(format, "{}", val),
+        use self::ExprKind::*;
+        match self.kind {
+            Value(ref val) => write!(format, "{}", val),
             Add(ref left, ref right) => write!(format, "{} + {}", left, right),
             Sub(ref left, ref right) => write!(format, "{} - {}", left, right),
             Mul(ref left, ref right) => write!(format, "{} * {}", left, right),
             Div(ref left, ref right) => write!(format, "{} / {}", left, right),
             Paren(ref expr) => write!(format, "({})", expr),
+            Neg(ref expr) => write!(format, "-{}", expr),
+            Plus(ref expr) => write!(format, "+{}", expr),
+            Pow(ref expr, exponent) if exponent.is_integer() => {
+                write!(format, "{}^{}", expr, exponent)
+            }
+            Pow(ref expr, exponent) => write!(format, "{}^({})", expr, exponent),
+            Call(function, ref arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+                write!(format, "{}({})", function, arguments.join(", "))
+            }
+            Convert(ref expr, ref unit) => write!(format, "{} to {}", expr, unit.symbol),
+            Variable(ref name) => write!(format, "{}", name),
+            Let(ref name, ref expr) => write!(format, "let {} = {}", name, expr),
+            Sequence(ref statements) => {
+                let statements: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+                write!(format, "{}", statements.join("; "))
+            }
         }
     }
 }
 
 impl Debug for Expr {
     fn fmt(&self, format: &mut Formatter<'_>) -> fmt::Result {
-        use self::Expr::*;
-        match *self {
-            Value(val) => write!(format, "{}", val),
+        use self::ExprKind::*;
+        match self.kind {
+            Value(ref val) => write!(format, "{}", val),
             Add(ref left, ref right) => write!(format, "({:?} + {:?})", left, right),
             Sub(ref left, ref right) => write!(format, "({:?} - {:?})", left, right),
             Mul(ref left, ref right) => write!(format, "({:?} * {:?})", left, right),
             Div(ref left, ref right) => write!(format, "({:?} / {:?})", left, right),
             Paren(ref expr) => write!(format, "[{:?}]", expr),
+            Neg(ref expr) => write!(format, "(-{:?})", expr),
+            Plus(ref expr) => write!(format, "(+{:?})", expr),
+            Pow(ref expr, exponent) => write!(format, "({:?} ^ {})", expr, exponent),
+            Call(function, ref arguments) => write!(format, "{}{:?}", function, arguments),
+            Convert(ref expr, ref unit) => write!(format, "({:?} to {})", expr, unit.symbol),
+            Variable(ref name) => write!(format, "{}", name),
+            Let(ref name, ref expr) => write!(format, "(let {} = {:?})", name, expr),
+            Sequence(ref statements) => write!(format, "{:?}", statements),
         }
     }
 }
 
-fn parens(input: &str) -> IResult<&str, Expr> {
-    delimited(
-        multispace0,
-        delimited(tag("("), map(expr, |e| Expr::Paren(Box::new(e))), tag(")")),
-        multispace0,
+fn parens(input: Span<'_>) -> PResult<'_, Expr> {
+    map(
+        ws(consumed(delimited(tag("("), expr, tag(")")))),
+        |(text, e)| Expr::new(ExprKind::Paren(Box::new(e)), source_span(text)),
     )
     .parse(input)
 }
 
-fn ws<'a, F, O, E: ParseError<&'a str>>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
+fn ws<'a, F, O>(inner: F) -> impl FnMut(Span<'a>) -> PResult<'a, O>
 where
-    F: Parser<&'a str, O, E>,
+    F: Parser<Span<'a>, O, ParseFailure<Span<'a>>>,
 {
     delimited(multispace0, inner, multispace0)
 }
 
-fn factor(input: &str) -> IResult<&str, Expr> {
-    alt((map(ws(concrete_number), Expr::Value), parens)).parse(input)
+fn variable_name(input: Span<'_>) -> PResult<'_, Span<'_>> {
+    verify(identifier, |w: &Span| !KEYWORDS.contains(w.fragment()))(input)
+}
+
+/// A variable; `_` is another name for `ans`.
+fn variable(input: Span<'_>) -> PResult<'_, Expr> {
+    map(variable_name, |name| {
+        let kind = match *name.fragment() {
+            "_" => ExprKind::Variable("ans".to_owned()),
+            name => ExprKind::Variable(name.to_owned()),
+        };
+        Expr::new(kind, source_span(name))
+    })(input)
+}
+
+/// A function call such as `sqrt(x)` or `atan2(y, x)`. A name followed by
+/// `(` must be a known function; the number of arguments is only checked
+/// when the call is evaluated.
+fn call(input: Span<'_>) -> PResult<'_, Expr> {
+    let (rest, (text, (name, arguments))) = consumed(pair(
+        variable_name,
+        preceded(
+            multispace0,
+            delimited(tag("("), separated_list0(tag(","), expr), tag(")")),
+        ),
+    ))(input)?;
+    match functions::lookup(name.fragment()) {
+        Some(function) => Ok((
+            rest,
+            Expr::new(ExprKind::Call(function, arguments), source_span(text)),
+        )),
+        None => Err(nom::Err::Failure(ParseFailure::UnknownFunction(name))),
+    }
+}
+
+fn factor(input: Span<'_>) -> PResult<'_, Expr> {
+    alt((ws(concrete_number), parens, ws(call), ws(variable))).parse(input)
+}
+
+/// The power in `x^2`, `x**-1` or `x^(2/3)`.
+fn power_exponent(input: Span<'_>) -> PResult<'_, Rational> {
+    preceded(
+        ws(alt((tag("^"), tag("**")))),
+        alt((
+            delimited(
+                ws(tag("(")),
+                map(
+                    pair(
+                        parse_integer,
+                        opt(preceded(ws(tag("/")), parse_denominator)),
+                    ),
+                    |(numerator, denominator)| Rational::new(numerator, denominator.unwrap_or(1)),
+                ),
+                ws(tag(")")),
+            ),
+            map(ws(parse_integer), Rational::integer),
+        )),
+    )(input)
+}
+
+/// Whether `text`, which parsed as a concrete number, has a unit after its
+/// magnitude.
+fn has_unit(text: &str) -> bool {
+    double::<_, ()>(text.trim()).is_ok_and(|(unit, _)| !unit.trim().is_empty())
+}
+
+/// A factor, optionally raised to a power. Powers bind more tightly than
+/// `*` and `/`. A power after a number with a unit is rejected, since
+/// `2 m ^ 2` could mean `(2 m)^2` or `2 m^2`.
+fn power(input: Span<'_>) -> PResult<'_, Expr> {
+    let (input, (text, base)) = consumed(factor)(input)?;
+    let (input, exponent) = opt(consumed(power_exponent))(input)?;
+    match exponent {
+        Some((_, exponent))
+            if matches!(base.kind, ExprKind::Value(_)) && has_unit(text.fragment()) =>
+        {
+            Err(nom::Err::Failure(ParseFailure::AmbiguousPower(
+                text, exponent,
+            )))
+        }
+        Some((text, exponent)) => {
+            let span = SourceSpan::new(base.span.start, source_span(text).end);
+            let kind = ExprKind::Pow(Box::new(base), exponent);
+            Ok((input, Expr::new(kind, span)))
+        }
+        None => Ok((input, base)),
+    }
+}
+
+/// A power with any number of prefix `-` and `+` signs.
+fn unary(input: Span<'_>) -> PResult<'_, Expr> {
+    let (rest, sign) = opt(ws(consumed(one_of("+-"))))(input)?;
+    let Some((text, sign)) = sign else {
+        return power(input);
+    };
+    let (rest, operand) = unary(rest)?;
+    let span = SourceSpan::new(text.location_offset(), operand.span.end);
+    let kind = match sign {
+        '-' => ExprKind::Neg(Box::new(operand)),
+        _ => ExprKind::Plus(Box::new(operand)),
+    };
+    Ok((rest, Expr::new(kind, span)))
 }
 
 fn fold_exprs(initial: Expr, remainder: Vec<(Oper, Expr)>) -> Expr {
     remainder.into_iter().fold(initial, |acc, pair| {
         let (oper, expr) = pair;
-        match oper {
-            Oper::Add => Expr::Add(Box::new(acc), Box::new(expr)),
-            Oper::Sub => Expr::Sub(Box::new(acc), Box::new(expr)),
-            Oper::Mul => Expr::Mul(Box::new(acc), Box::new(expr)),
-            Oper::Div => Expr::Div(Box::new(acc), Box::new(expr)),
-        }
+        let span = SourceSpan::new(acc.span.start, expr.span.end);
+        let kind = match oper {
+            Oper::Add => ExprKind::Add(Box::new(acc), Box::new(expr)),
+            Oper::Sub => ExprKind::Sub(Box::new(acc), Box::new(expr)),
+            Oper::Mul => ExprKind::Mul(Box::new(acc), Box::new(expr)),
+            Oper::Div => ExprKind::Div(Box::new(acc), Box::new(expr)),
+        };
+        Expr::new(kind, span)
     })
 }
 
-fn term_mul(input: &str) -> IResult<&str, (Oper, Expr)> {
-    let (input, mul) = preceded(ws(tag("*")), ws(factor)).parse(input)?;
+fn term_mul(input: Span<'_>) -> PResult<'_, (Oper, Expr)> {
+    let (input, mul) = preceded(ws(tag("*")), ws(unary)).parse(input)?;
     Ok((input, (Oper::Mul, mul)))
 }
 
-fn term_div(input: &str) -> IResult<&str, (Oper, Expr)> {
-    let (input, div) = preceded(ws(tag("/")), ws(factor)).parse(input)?;
+fn term_div(input: Span<'_>) -> PResult<'_, (Oper, Expr)> {
+    let (input, div) = preceded(ws(tag("/")), ws(unary)).parse(input)?;
     Ok((input, (Oper::Div, div)))
 }
 
-fn term(input: &str) -> IResult<&str, Expr> {
-    let (input, initial) = factor(input)?;
+fn term(input: Span<'_>) -> PResult<'_, Expr> {
+    let (input, initial) = unary(input)?;
     let (i, remainder) = many0(alt((term_mul, term_div))).parse(input)?;
 
     Ok((i, fold_exprs(initial, remainder)))
 }
 
-fn expr_add(input: &str) -> IResult<&str, (Oper, Expr)> {
+fn expr_add(input: Span<'_>) -> PResult<'_, (Oper, Expr)> {
     let (input, add) = preceded(ws(tag("+")), term).parse(input)?;
     Ok((input, (Oper::Add, add)))
 }
 
-fn expr_sub(input: &str) -> IResult<&str, (Oper, Expr)> {
+fn expr_sub(input: Span<'_>) -> PResult<'_, (Oper, Expr)> {
     let (input, sub) = preceded(ws(tag("-")), term).parse(input)?;
     Ok((input, (Oper::Sub, sub)))
 }
 
-fn expr(input: &str) -> IResult<&str, Expr> {
+fn expr(input: Span<'_>) -> PResult<'_, Expr> {
     let (input, initial) = term(input)?;
     let (input, remainder) = many0(alt((expr_add, expr_sub))).parse(input)?;
+    let (input, target) = opt(conversion_target).parse(input)?;
 
-    Ok((input, fold_exprs(initial, remainder)))
+    let expr = fold_exprs(initial, remainder);
+    match target {
+        Some((unit, unit_span)) => {
+            let span = SourceSpan::new(expr.span.start, unit_span.end);
+            Ok((
+                input,
+                Expr::new(ExprKind::Convert(Box::new(expr), unit), span),
+            ))
+        }
+        None => Ok((input, expr)),
+    }
 }
 
-pub fn evaluate_physical_equation(input: &str) -> Result<ConcreteNumber, CustomError> {
-    let input = format!("({})", input);
-    let (remainder, expr) = expr(input.as_str()).or_else(|e| {
-        Err(CustomError::ParseError(format!(
-            "ERR: Could not parse input: {e}"
-        )))
+fn let_binding(input: Span<'_>) -> PResult<'_, Expr> {
+    let (input, (text, (name, value))) = consumed(pair(
+        preceded(ws(keyword("let")), ws(variable_name)),
+        preceded(tag("="), expr),
+    ))(input)?;
+    let span = SourceSpan::new(text.location_offset(), value.span.end);
+    Ok((
+        input,
+        Expr::new(
+            ExprKind::Let(name.fragment().to_string(), Box::new(value)),
+            span,
+        ),
+    ))
+}
+
+/// One or more `let` bindings or expressions separated by `;`.
+fn statements(input: Span<'_>) -> PResult<'_, Expr> {
+    let (input, mut statements) = separated_list1(tag(";"), alt((let_binding, expr)))(input)?;
+    let (input, _) = opt(tag(";"))(input)?;
+    if statements.len() == 1 {
+        return Ok((input, statements.remove(0)));
+    }
+    let span = SourceSpan::new(
+        statements[0].span.start,
+        statements[statements.len() - 1].span.end,
+    );
+    Ok((input, Expr::new(ExprKind::Sequence(statements), span)))
+}
+
+/// Replaces the reconstructed expression text in an error's trail with the
+/// text that was actually typed.
+fn with_source_text(error: CustomError, input: &str) -> CustomError {
+    match error {
+        CustomError::InSubExpression {
+            span,
+            variables,
+            source,
+            ..
+        } => CustomError::InSubExpression {
+            expression: input[span.start..span.end].to_owned(),
+            span,
+            variables,
+            source: Box::new(with_source_text(*source, input)),
+        },
+        error => error,
+    }
+}
+
+pub fn parse(input: &str) -> Result<Expr, CustomError> {
+    let (remainder, expr) = statements(Span::new(input)).map_err(|e| match e {
+        nom::Err::Failure(ParseFailure::UnknownUnit(symbol)) => CustomError::UnknownUnit {
+            symbol: symbol.fragment().to_string(),
+            span: source_span(symbol),
+        },
+        nom::Err::Failure(ParseFailure::UnknownFunction(name)) => CustomError::UnknownFunction {
+            name: name.fragment().to_string(),
+            span: source_span(name),
+        },
+        nom::Err::Failure(ParseFailure::AmbiguousPower(quantity, exponent)) => {
+            let text = quantity.fragment();
+            let start = quantity.location_offset() + text.len() - text.trim_start().len();
+            CustomError::AmbiguousPower {
+                quantity: text.trim().to_owned(),
+                exponent,
+                span: SourceSpan::new(start, start + text.trim().len()),
+            }
+        }
+        nom::Err::Failure(ParseFailure::ExponentOutOfRange(_)) => CustomError::ExponentOutOfRange,
+        nom::Err::Error(ParseFailure::Nom(at, _)) | nom::Err::Failure(ParseFailure::Nom(at, _)) => {
+            CustomError::ParseError {
+                found: at.fragment().to_string(),
+                span: SourceSpan::new(at.location_offset(), input.len()),
+            }
+        }
+        _ => CustomError::ParseError {
+            found: input.to_owned(),
+            span: SourceSpan::new(0, input.len()),
+        },
     })?;
-    if !remainder.is_empty() {
-        return Err(CustomError::ParseError(format!(
-            "ERR: Could not parse full input. Remaining input: {remainder}"
-        )));
+    if !remainder.fragment().is_empty() {
+        Err(CustomError::ParseError {
+            found: remainder.fragment().to_string(),
+            span: source_span(remainder),
+        })
     } else {
-        let result = expr.evaluate();
-        result
+        Ok(expr)
     }
 }
+
+pub fn evaluate_physical_equation(input: &str) -> Result<ConcreteNumber, CustomError> {
+    evaluate_in(input, &mut Environment::new())
+}
+
+pub fn evaluate_in(
+    input: &str,
+    environment: &mut Environment,
+) -> Result<ConcreteNumber, CustomError> {
+    parse(input)?
+        .evaluate(environment)
+        .map_err(|error| with_source_text(error, input))
+}
@@ -0,0 +1,80 @@
+/// An SI prefix, e.g. the `k` in `km`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prefix {
+    pub symbol: &'static str,
+    pub name: &'static str,
+    pub factor: f64,
+}
+
+const fn prefix(symbol: &'static str, name: &'static str, factor: f64) -> Prefix {
+    Prefix {
+        symbol,
+        name,
+        factor,
+    }
+}
+
+/// The SI prefixes from quecto to quetta. Micro is accepted as the micro sign
+/// (U+00B5), the Greek letter mu (U+03BC), and the ASCII fallback `u`.
+pub const SI_PREFIXES: [Prefix; 26] = [
+    prefix("q", "quecto", 1e-30),
+    prefix("r", "ronto", 1e-27),
+    prefix("y", "yocto", 1e-24),
+    prefix("z", "zepto", 1e-21),
+    prefix("a", "atto", 1e-18),
+    prefix("f", "femto", 1e-15),
+    prefix("p", "pico", 1e-12),
+    prefix("n", "nano", 1e-9),
+    prefix("µ", "micro", 1e-6),
+    prefix("μ", "micro", 1e-6),
+    prefix("u", "micro", 1e-6),
+    prefix("m", "milli", 1e-3),
+    prefix("c", "centi", 1e-2),
+    prefix("d", "deci", 1e-1),
+    prefix("da", "deca", 1e1),
+    prefix("h", "hecto", 1e2),
+    prefix("k", "kilo", 1e3),
+    prefix("M", "mega", 1e6),
+    prefix("G", "giga", 1e9),
+    prefix("T", "tera", 1e12),
+    prefix("P", "peta", 1e15),
+    prefix("E", "exa", 1e18),
+    prefix("Z", "zetta", 1e21),
+    prefix("Y", "yotta", 1e24),
+    prefix("R", "ronna", 1e27),
+    prefix("Q", "quetta", 1e30),
+];
+
+/// Every way of splitting `symbol` into an SI prefix and a non-empty remainder,
+/// longest prefix first (so `dam` is tried as `da` + `m` before `d` + `am`).
+pub fn split_prefix(symbol: &str) -> Vec<(Prefix, &str)> {
+    let mut splits: Vec<(Prefix, &str)> = SI_PREFIXES
+        .iter()
+        .filter_map(|prefix| {
+            symbol
+                .strip_prefix(prefix.symbol)
+                .filter(|rest| !rest.is_empty())
+                .map(|rest| (*prefix, rest))
+        })
+        .collect();
+    splits.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.symbol.len()));
+    splits
+}
+
+/// The engineering prefix for `magnitude`, the power of 1000 that leaves
+/// between 1 and 1000 of the prefixed unit, e.g. micro for 0.000003 or giga
+/// for 1500000000. There is none for magnitudes already in that range, for
+/// zero, or beyond the prefixes at either end.
+pub fn engineering(magnitude: f64) -> Option<Prefix> {
+    if magnitude == 0.0 || !magnitude.is_finite() {
+        return None;
+    }
+    let exponent = (magnitude.abs().log10() / 3.0).floor() as i32 * 3;
+    if exponent == 0 || exponent.abs() > 30 {
+        return None;
+    }
+    SI_PREFIXES
+        .iter()
+        .find(|prefix| prefix.factor.log10().round() as i32 == exponent)
+        .copied()
+}
@@ -0,0 +1,196 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// An exact fraction, used for dimension exponents so that quantities such as
+/// `V/Hz^(1/2)` can be represented. It is always kept in lowest terms with a
+/// positive denominator, so equal values compare equal. The parts are `i16`,
+/// which is plenty for exponents and keeps physical quantities small.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i16,
+    denominator: i16,
+}
+
+const fn gcd(a: i32, b: i32) -> i32 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational::integer(0);
+    pub const ONE: Rational = Rational::integer(1);
+
+    /// The fraction `numerator / denominator` in lowest terms. Panics if the
+    /// denominator is zero.
+    pub const fn new(numerator: i16, denominator: i16) -> Self {
+        Self::reduced(numerator as i32, denominator as i32)
+    }
+
+    /// Reduces a fraction that may have been computed outside the range of
+    /// `i16`. Panics if it is still out of range in lowest terms.
+    const fn reduced(numerator: i32, denominator: i32) -> Self {
+        match Self::checked_reduced(numerator, denominator) {
+            Some(rational) => rational,
+            None => panic!("a rational is out of range"),
+        }
+    }
+
+    /// Reduces a fraction that may have been computed outside the range of
+    /// `i16`, or returns `None` if it is still out of range in lowest terms.
+    /// Panics if the denominator is zero.
+    const fn checked_reduced(numerator: i32, denominator: i32) -> Option<Self> {
+        assert!(denominator != 0, "a rational needs a non-zero denominator");
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+        if numerator < i16::MIN as i32
+            || numerator > i16::MAX as i32
+            || denominator > i16::MAX as i32
+        {
+            return None;
+        }
+        Some(Self {
+            numerator: numerator as i16,
+            denominator: denominator as i16,
+        })
+    }
+
+    /// `self + rhs`, or `None` if the result is out of range.
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::checked_reduced(
+            self.numerator as i32 * rhs.denominator as i32
+                + rhs.numerator as i32 * self.denominator as i32,
+            self.denominator as i32 * rhs.denominator as i32,
+        )
+    }
+
+    /// `self - rhs`, or `None` if the result is out of range.
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Self::checked_reduced(
+            self.numerator as i32 * rhs.denominator as i32
+                - rhs.numerator as i32 * self.denominator as i32,
+            self.denominator as i32 * rhs.denominator as i32,
+        )
+    }
+
+    /// `self * rhs`, or `None` if the result is out of range.
+    pub const fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Self::checked_reduced(
+            self.numerator as i32 * rhs.numerator as i32,
+            self.denominator as i32 * rhs.denominator as i32,
+        )
+    }
+
+    pub const fn integer(value: i16) -> Self {
+        Self {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+
+    pub const fn numerator(self) -> i16 {
+        self.numerator
+    }
+
+    pub const fn denominator(self) -> i16 {
+        self.denominator
+    }
+
+    pub const fn is_integer(self) -> bool {
+        self.denominator == 1
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl From<i16> for Rational {
+    fn from(value: i16) -> Self {
+        Rational::integer(value)
+    }
+}
+
+impl PartialEq<i16> for Rational {
+    fn eq(&self, other: &i16) -> bool {
+        self.is_integer() && self.numerator == *other
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lhs = self.numerator as i32 * other.denominator as i32;
+        let rhs = other.numerator as i32 * self.denominator as i32;
+        lhs.cmp(&rhs)
+    }
+}
+
+/// Writes integers as they are and fractions as `1/2` or `-3/2`.
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.denominator {
+            1 => write!(f, "{}", self.numerator),
+            _ => write!(f, "{}/{}", self.numerator, self.denominator),
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    /// Panics if the sum is out of range; see [`Rational::checked_add`].
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect("a rational is out of range")
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    /// Panics if the difference is out of range; see
+    /// [`Rational::checked_sub`].
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs).expect("a rational is out of range")
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    /// Panics if the product is out of range; see
+    /// [`Rational::checked_mul`].
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.checked_mul(rhs).expect("a rational is out of range")
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    /// Panics when dividing by zero, like integer division.
+    fn div(self, rhs: Self) -> Self::Output {
+        Rational::reduced(
+            self.numerator as i32 * rhs.denominator as i32,
+            self.denominator as i32 * rhs.numerator as i32,
+        )
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Self::Output {
+        Rational::reduced(-(self.numerator as i32), self.denominator as i32)
+    }
+}
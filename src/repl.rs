@@ -0,0 +1,245 @@
+//! The interactive prompt, started when the CLI is run on a terminal without
+//! an expression to evaluate.
+
+use std::path::PathBuf;
+
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use crate::output::DisplayOptions;
+use unit_parser::{
+    constants, functions, units, ConcreteNumber, Environment, PhysicalQuantityBuilder,
+};
+
+const HELP: &str = "\
+Enter an expression such as `3 km / 20 min to km/h` to evaluate it.
+`let name = value` defines a variable, and `ans` or `_` is the previous result.
+
+:units        list the units that can be used
+:constants    list the physical constants that can be used
+:functions    list the functions that can be called
+:dim <expr>   show the physical quantity of an expression
+:angles on    track angle as a dimension, so rad/s is not Hz (off by default)
+:help         show this help
+:quit         leave (as does Ctrl-D)";
+
+/// Where history is kept between sessions, if there is a home directory.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".unit_parser_history"))
+}
+
+/// What the prompt does in response to a line.
+#[derive(Debug, PartialEq)]
+enum Response {
+    /// Text for stdout, which may be empty.
+    Output(String),
+    /// An error report for stderr.
+    Error(String),
+    Quit,
+}
+
+/// Evaluates a line or runs a `:` command. A result is kept as `ans`.
+fn respond(line: &str, options: DisplayOptions, environment: &mut Environment) -> Response {
+    match line.split_once(' ').unwrap_or((line, "")) {
+        (":quit" | ":q", _) => Response::Quit,
+        (":help", _) => Response::Output(HELP.to_owned()),
+        (":units", _) => Response::Output(units_table()),
+        (":constants", _) => Response::Output(constants_table()),
+        (":functions", _) => Response::Output(function_names()),
+        (":angles", setting) => Response::Output(match setting.trim() {
+            "on" => {
+                environment.set_track_angles(true);
+                String::new()
+            }
+            "off" => {
+                environment.set_track_angles(false);
+                String::new()
+            }
+            "" if environment.tracks_angles() => "angles are tracked".to_owned(),
+            "" => "angles are dimensionless".to_owned(),
+            setting => format!(
+                "unknown setting `{}`, try :angles on or :angles off",
+                setting
+            ),
+        }),
+        (":dim", expression) => {
+            let expression = expression.trim();
+            // Bindings made here only exist for the one expression.
+            match unit_parser::evaluate_in(expression, &mut environment.clone()) {
+                Ok(result) => Response::Output(dimensions(&result)),
+                Err(error) => Response::Error(crate::error_report(expression, &error)),
+            }
+        }
+        (command, _) if command.starts_with(':') => {
+            Response::Output(format!("unknown command `{}`, try :help", command))
+        }
+        _ => match unit_parser::evaluate_in(line, environment) {
+            Ok(result) => {
+                let output = options.format(&options.present(result.clone()));
+                environment.set("ans", result);
+                Response::Output(output)
+            }
+            Err(error) => Response::Error(crate::error_report(line, &error)),
+        },
+    }
+}
+
+pub fn run(options: DisplayOptions, mut environment: Environment) -> rustyline::Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let history = history_path();
+    if let Some(path) = &history {
+        // There is no history file until the first session has ended.
+        let _ = editor.load_history(path);
+    }
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+        match respond(line, options, &mut environment) {
+            Response::Output(output) if output.is_empty() => {}
+            Response::Output(output) => println!("{}", output),
+            Response::Error(report) => eprintln!("{}", report),
+            Response::Quit => break,
+        }
+    }
+    if let Some(path) = &history {
+        editor.save_history(path)?;
+    }
+    Ok(())
+}
+
+fn units_table() -> String {
+    let lines: Vec<String> = units::UNITS
+        .iter()
+        .map(|unit| {
+            let line = format!(
+                "{:<8} {:<22} {}",
+                unit.symbol,
+                unit.physical_quantity.kind(),
+                unit.aliases.join(", ")
+            );
+            line.trim_end().to_owned()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// The constants with their values in scientific notation, since many are
+/// tiny, like the Planck constant.
+fn constants_table() -> String {
+    let lines: Vec<String> = constants::CONSTANTS
+        .iter()
+        .map(|constant| {
+            let line = format!(
+                "{:<8} {:<36} {:e} {}",
+                constant.symbol, constant.name, constant.value, constant.physical_quantity
+            );
+            line.trim_end().to_owned()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn function_names() -> String {
+    let names: Vec<&str> = functions::FUNCTIONS.iter().map(|f| f.name()).collect();
+    names.join(", ")
+}
+
+fn dimensions(result: &ConcreteNumber) -> String {
+    let physical_quantity = result.physical_quantity();
+    if physical_quantity == PhysicalQuantityBuilder::new().build() {
+        physical_quantity.kind()
+    } else {
+        format!("{} ({})", physical_quantity.kind(), physical_quantity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use unit_parser::{notation::Notation, units::UnitSystem, Environment};
+
+    use super::{respond, Response};
+    use crate::output::DisplayOptions;
+
+    const OPTIONS: DisplayOptions = DisplayOptions {
+        system: UnitSystem::SI,
+        best_unit: false,
+        notation: Notation::Plain,
+        precision: None,
+    };
+
+    fn output(text: &str) -> Response {
+        Response::Output(text.to_owned())
+    }
+
+    #[test]
+    fn results_are_kept_as_ans() {
+        let mut environment = Environment::new();
+        let mut respond = |line| respond(line, OPTIONS, &mut environment);
+        assert_eq!(respond("3 m"), output("3 m"));
+        assert_eq!(respond("ans * 2"), output("6 m"));
+        assert_eq!(respond("ans * 2"), output("12 m"));
+        assert!(matches!(respond("ans + 1 s"), Response::Error(_)));
+        assert_eq!(respond("ans"), output("12 m"));
+    }
+
+    #[test]
+    fn dim_shows_the_kind_without_binding_anything() {
+        let mut environment = Environment::new();
+        let mut respond = |line| respond(line, OPTIONS, &mut environment);
+        assert_eq!(respond(":dim 3 N * 2 m"), output("Energy (J)"));
+        assert_eq!(respond(":dim 2"), output("Dimensionless"));
+        assert_eq!(
+            respond(":dim 1 m +"),
+            Response::Error("ERROR: could not parse `+`\n  1 m +\n      ^".to_owned())
+        );
+        assert!(matches!(
+            respond(":dim let x = 1 m; x"),
+            Response::Output(_)
+        ));
+        assert!(matches!(respond("x"), Response::Error(_)));
+    }
+
+    #[test]
+    fn angles_can_be_switched_on_and_off() {
+        let mut environment = Environment::new();
+        let mut respond = |line| respond(line, OPTIONS, &mut environment);
+        assert_eq!(respond(":angles"), output("angles are dimensionless"));
+        assert_eq!(respond(":angles on"), output(""));
+        assert_eq!(respond(":angles"), output("angles are tracked"));
+        assert_eq!(respond(":angles off"), output(""));
+        assert_eq!(respond(":angles"), output("angles are dimensionless"));
+        assert_eq!(
+            respond(":angles maybe"),
+            output("unknown setting `maybe`, try :angles on or :angles off")
+        );
+    }
+
+    #[test]
+    fn unknown_commands_and_quitting() {
+        let mut environment = Environment::new();
+        assert_eq!(
+            respond(":bogus", OPTIONS, &mut environment),
+            output("unknown command `:bogus`, try :help")
+        );
+        assert_eq!(respond(":q", OPTIONS, &mut environment), Response::Quit);
+        assert_eq!(respond(":quit", OPTIONS, &mut environment), Response::Quit);
+    }
+
+    #[test]
+    fn constants_are_listed_in_scientific_notation() {
+        let Response::Output(table) = respond(":constants", OPTIONS, &mut Environment::new())
+        else {
+            panic!("no constants table");
+        };
+        let planck = table.lines().find(|line| line.starts_with("h ")).unwrap();
+        assert!(planck.contains("6.62607015e-34"), "{planck}");
+    }
+}
@@ -0,0 +1,404 @@
+use std::{f64::consts::PI, str::FromStr};
+
+use crate::{prefix, DisplayUnit, PhysicalQuantity, PhysicalQuantityBuilder};
+
+/// A named unit: its symbol, any other spellings accepted for it, the
+/// physical quantity it measures, and the size of one of it in coherent SI.
+/// Affine temperature scales also have an offset, the distance from their zero
+/// to absolute zero in the unit itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub aliases: &'static [&'static str],
+    pub physical_quantity: PhysicalQuantity,
+    pub factor: f64,
+    pub offset: f64,
+    pub prefixable: bool,
+}
+
+const fn unit(
+    symbol: &'static str,
+    aliases: &'static [&'static str],
+    physical_quantity: PhysicalQuantity,
+    factor: f64,
+    prefixable: bool,
+) -> Unit {
+    Unit {
+        symbol,
+        aliases,
+        physical_quantity,
+        factor,
+        offset: 0.0,
+        prefixable,
+    }
+}
+
+const fn affine_unit(
+    symbol: &'static str,
+    aliases: &'static [&'static str],
+    factor: f64,
+    offset: f64,
+) -> Unit {
+    Unit {
+        symbol,
+        aliases,
+        physical_quantity: TEMPERATURE,
+        factor,
+        offset,
+        prefixable: false,
+    }
+}
+
+pub(crate) const DIMENSIONLESS: PhysicalQuantity = PhysicalQuantityBuilder::new().build();
+pub(crate) const ANGLE: PhysicalQuantity = PhysicalQuantityBuilder::new().angle(1).build();
+pub(crate) const TEMPERATURE: PhysicalQuantity =
+    PhysicalQuantityBuilder::new().temperature(1).build();
+pub(crate) const TIME: PhysicalQuantity = PhysicalQuantityBuilder::new().time(1).build();
+pub(crate) const LENGTH: PhysicalQuantity = PhysicalQuantityBuilder::new().length(1).build();
+pub(crate) const MASS: PhysicalQuantity = PhysicalQuantityBuilder::new().mass(1).build();
+pub(crate) const AREA: PhysicalQuantity = PhysicalQuantityBuilder::new().length(2).build();
+pub(crate) const VOLUME: PhysicalQuantity = PhysicalQuantityBuilder::new().length(3).build();
+pub(crate) const PRESSURE: PhysicalQuantity = PhysicalQuantityBuilder::new()
+    .time(-2)
+    .length(-1)
+    .mass(1)
+    .build();
+pub(crate) const ENERGY: PhysicalQuantity = PhysicalQuantityBuilder::new()
+    .time(-2)
+    .length(2)
+    .mass(1)
+    .build();
+pub(crate) const FORCE: PhysicalQuantity = PhysicalQuantityBuilder::new()
+    .time(-2)
+    .length(1)
+    .mass(1)
+    .build();
+pub(crate) const POWER: PhysicalQuantity = PhysicalQuantityBuilder::new()
+    .time(-3)
+    .length(2)
+    .mass(1)
+    .build();
+pub(crate) const SPEED: PhysicalQuantity =
+    PhysicalQuantityBuilder::new().time(-1).length(1).build();
+
+const INCH: f64 = 0.0254;
+const POUND: f64 = 0.453_592_37;
+pub(crate) const STANDARD_GRAVITY: f64 = 9.806_65;
+const POUND_FORCE: f64 = POUND * STANDARD_GRAVITY;
+const US_GALLON: f64 = 231.0 * INCH * INCH * INCH;
+const IMPERIAL_GALLON: f64 = 4.546_09e-3;
+
+/// Every unit the parser knows. The kilogram is the SI base unit of mass, but
+/// prefixes attach to the gram, so `g` has a factor of 1e-3 and `kg` is not
+/// itself prefixable.
+pub static UNITS: &[Unit] = &[
+    // SI base units
+    unit("s", &[], TIME, 1.0, true),
+    unit("m", &[], LENGTH, 1.0, true),
+    unit("g", &[], MASS, 1e-3, true),
+    unit("kg", &[], MASS, 1.0, false),
+    unit(
+        "A",
+        &[],
+        PhysicalQuantityBuilder::new().current(1).build(),
+        1.0,
+        true,
+    ),
+    unit("K", &[], TEMPERATURE, 1.0, true),
+    unit(
+        "mol",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .amount_of_substance(1)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "cd",
+        &[],
+        PhysicalQuantityBuilder::new().luminous_intensity(1).build(),
+        1.0,
+        true,
+    ),
+    // SI derived units
+    unit(
+        "Hz",
+        &[],
+        PhysicalQuantityBuilder::new().time(-1).build(),
+        1.0,
+        true,
+    ),
+    unit("N", &[], FORCE, 1.0, true),
+    unit("Pa", &[], PRESSURE, 1.0, true),
+    unit("J", &[], ENERGY, 1.0, true),
+    unit("W", &[], POWER, 1.0, true),
+    unit(
+        "C",
+        &[],
+        PhysicalQuantityBuilder::new().time(1).current(1).build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "V",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .time(-3)
+            .length(2)
+            .mass(1)
+            .current(-1)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "Wb",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .time(-2)
+            .length(2)
+            .mass(1)
+            .current(-1)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "T",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .time(-2)
+            .mass(1)
+            .current(-1)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "F",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .time(4)
+            .length(-2)
+            .mass(-1)
+            .current(2)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "Ω",
+        &["ohm"],
+        PhysicalQuantityBuilder::new()
+            .time(-3)
+            .length(2)
+            .mass(1)
+            .current(-2)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "S",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .time(3)
+            .length(-2)
+            .mass(-1)
+            .current(2)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "H",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .time(-2)
+            .length(2)
+            .mass(1)
+            .current(-2)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit(
+        "kat",
+        &[],
+        PhysicalQuantityBuilder::new()
+            .time(-1)
+            .amount_of_substance(1)
+            .build(),
+        1.0,
+        true,
+    ),
+    unit("rad", &[], ANGLE, 1.0, true),
+    unit(
+        "sr",
+        &[],
+        PhysicalQuantityBuilder::new().angle(2).build(),
+        1.0,
+        true,
+    ),
+    unit("dimensionless", &[], DIMENSIONLESS, 1.0, false),
+    // Non-SI units accepted for use with the SI
+    unit("min", &["minute", "minutes"], TIME, 60.0, false),
+    unit("h", &["hr", "hour", "hours"], TIME, 3600.0, false),
+    unit("d", &["day", "days"], TIME, 86400.0, false),
+    unit("au", &[], LENGTH, 149_597_870_700.0, false),
+    unit("Å", &["angstrom"], LENGTH, 1e-10, false),
+    unit("ha", &["hectare"], AREA, 1e4, false),
+    unit("L", &["l", "litre", "liter"], VOLUME, 1e-3, true),
+    unit("t", &["tonne"], MASS, 1e3, true),
+    unit("Da", &["dalton"], MASS, 1.660_539_066_60e-27, true),
+    unit("eV", &["electronvolt"], ENERGY, 1.602_176_634e-19, true),
+    unit("Wh", &[], ENERGY, 3600.0, true),
+    unit("bar", &[], PRESSURE, 1e5, true),
+    unit("atm", &[], PRESSURE, 101_325.0, false),
+    // Angles besides the radian. Like the radian, they are dimensionless
+    // unless angles are tracked.
+    unit("°", &["deg", "degree", "degrees"], ANGLE, PI / 180.0, false),
+    unit("arcmin", &["arcminute"], ANGLE, PI / 10_800.0, false),
+    unit("arcsec", &["arcsecond"], ANGLE, PI / 648_000.0, false),
+    unit("grad", &["gon"], ANGLE, PI / 200.0, false),
+    unit("turn", &["rev", "revolution"], ANGLE, 2.0 * PI, false),
+    unit(
+        "rpm",
+        &[],
+        PhysicalQuantityBuilder::new().time(-1).angle(1).build(),
+        2.0 * PI / 60.0,
+        false,
+    ),
+    // Imperial and US customary units. The inch is spelt out because `in` is
+    // the conversion keyword. Where the two systems differ, the bare symbol is
+    // the US unit and the imperial one is prefixed with `imp`.
+    unit("inch", &["inches"], LENGTH, INCH, false),
+    unit("ft", &["foot", "feet"], LENGTH, 0.3048, false),
+    unit("yd", &["yard", "yards"], LENGTH, 0.9144, false),
+    unit("mi", &["mile", "miles"], LENGTH, 1_609.344, false),
+    unit("oz", &["ounce", "ounces"], MASS, POUND / 16.0, false),
+    unit("lb", &["lbs", "pound", "pounds"], MASS, POUND, false),
+    unit("st", &["stone"], MASS, 14.0 * POUND, false),
+    unit("lbf", &[], FORCE, POUND_FORCE, false),
+    unit("kip", &[], FORCE, 1000.0 * POUND_FORCE, false),
+    unit("floz", &[], VOLUME, US_GALLON / 128.0, false),
+    unit("pt", &["pint", "pints"], VOLUME, US_GALLON / 8.0, false),
+    unit("qt", &["quart", "quarts"], VOLUME, US_GALLON / 4.0, false),
+    unit("gal", &["gallon", "gallons"], VOLUME, US_GALLON, false),
+    unit("impfloz", &[], VOLUME, IMPERIAL_GALLON / 160.0, false),
+    unit("imppt", &[], VOLUME, IMPERIAL_GALLON / 8.0, false),
+    unit("impqt", &[], VOLUME, IMPERIAL_GALLON / 4.0, false),
+    unit("impgal", &[], VOLUME, IMPERIAL_GALLON, false),
+    unit("psi", &[], PRESSURE, POUND_FORCE / (INCH * INCH), false),
+    unit("BTU", &["Btu"], ENERGY, 1_055.055_852_62, false),
+    unit(
+        "hp",
+        &["horsepower"],
+        POWER,
+        550.0 * 0.3048 * POUND_FORCE,
+        false,
+    ),
+    unit("mph", &[], SPEED, 1_609.344 / 3600.0, false),
+    // Temperature scales. Celsius and Fahrenheit read as absolute
+    // temperatures when they stand alone after a number, and as differences
+    // (like the `Δ` forms) when part of a compound unit such as `W m^-1 °C^-1`.
+    affine_unit("°C", &["degC", "celsius"], 1.0, 273.15),
+    affine_unit("°F", &["degF", "fahrenheit"], 5.0 / 9.0, 459.67),
+    unit("°R", &["degR", "rankine"], TEMPERATURE, 5.0 / 9.0, false),
+    unit("Δ°C", &["deltaC"], TEMPERATURE, 1.0, false),
+    unit("Δ°F", &["deltaF"], TEMPERATURE, 5.0 / 9.0, false),
+];
+
+/// A family of units results can be shown in. SI leaves results in coherent
+/// SI units; the others show them in the system's customary unit for that
+/// physical quantity where it has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitSystem {
+    SI,
+    Imperial,
+    UsCustomary,
+}
+
+impl UnitSystem {
+    fn preferred_units(self) -> &'static [&'static str] {
+        match self {
+            UnitSystem::SI => &[],
+            UnitSystem::Imperial => &["ft", "lb", "lbf", "impgal", "psi", "BTU", "hp", "mph"],
+            UnitSystem::UsCustomary => &["ft", "lb", "lbf", "gal", "psi", "BTU", "hp", "mph"],
+        }
+    }
+
+    /// The unit this system uses for `physical_quantity`, if it has one.
+    pub fn display_unit(self, physical_quantity: PhysicalQuantity) -> Option<DisplayUnit> {
+        self.preferred_units()
+            .iter()
+            .filter_map(|symbol| lookup(symbol))
+            .find(|unit| unit.physical_quantity == physical_quantity)
+            .map(|unit| {
+                DisplayUnit::new(
+                    unit.symbol,
+                    unit.factor,
+                    unit.offset,
+                    unit.physical_quantity,
+                )
+            })
+    }
+}
+
+impl FromStr for UnitSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "si" | "metric" => Ok(UnitSystem::SI),
+            "imperial" | "uk" => Ok(UnitSystem::Imperial),
+            "us" | "customary" => Ok(UnitSystem::UsCustomary),
+            _ => Err(format!("Unknown unit system: {s}")),
+        }
+    }
+}
+
+/// The named coherent SI unit for `physical_quantity` that takes prefixes,
+/// such as `W` for power, or the gram for mass, since prefixes attach to it
+/// rather than the kilogram.
+pub fn prefixable_unit(physical_quantity: PhysicalQuantity) -> Option<&'static Unit> {
+    UNITS.iter().find(|unit| {
+        unit.prefixable
+            && unit.physical_quantity == physical_quantity
+            && (unit.factor == 1.0 || unit.symbol == "g")
+    })
+}
+
+/// Finds the unit with exactly this symbol or alias.
+pub fn lookup(symbol: &str) -> Option<&'static Unit> {
+    UNITS
+        .iter()
+        .find(|unit| unit.symbol == symbol || unit.aliases.contains(&symbol))
+}
+
+/// Finds the affine temperature scale with exactly this symbol or alias.
+pub fn affine(symbol: &str) -> Option<&'static Unit> {
+    lookup(symbol).filter(|unit| unit.offset != 0.0)
+}
+
+/// Resolves a (possibly prefixed) unit symbol into the factor that converts a
+/// magnitude in that unit to coherent SI, and its physical quantity.
+///
+/// An exact symbol always wins over a prefixed reading, so `m` is the metre
+/// rather than a bare milli, `cd` is the candela, `Pa` the pascal, `kat` the
+/// katal and `h` the hour. Only when no unit has that exact symbol is it split
+/// into an SI prefix and a prefixable unit, trying the longest prefix first.
+pub fn resolve(symbol: &str) -> Option<(f64, PhysicalQuantity)> {
+    if let Some(unit) = lookup(symbol) {
+        return Some((unit.factor, unit.physical_quantity));
+    }
+    prefix::split_prefix(symbol)
+        .into_iter()
+        .find_map(|(prefix, rest)| match lookup(rest) {
+            Some(unit) if unit.prefixable => {
+                Some((prefix.factor * unit.factor, unit.physical_quantity))
+            }
+            _ => None,
+        })
+}